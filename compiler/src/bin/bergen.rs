@@ -1,12 +1,14 @@
 extern crate bergen;
 extern crate clap;
 
-use bergen::brnfck::io_run;
-use bergen::parser::parse;
-use clap::{App, Arg};
-use std::fs::File;
+use bergen::brnfck::{io_run, Backend, CellWidth, Command, Eof, MachineConfig, Overflow};
+use bergen::parser::{stream, Span};
+use clap::{App, Arg, ArgMatches};
+use std::fs::{self, File};
 use std::io;
-use std::io::{BufReader, Read};
+use std::iter;
+use std::process;
+use std::time::Duration;
 
 fn main() {
     let matches = App::new("bergen")
@@ -21,20 +23,133 @@ fn main() {
                 .help("source for the bergen program to interpret")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("tape")
+                .short("t")
+                .long("tape")
+                .value_name("CELLS")
+                .help("number of cells on the tape of the machine")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("unbounded")
+                .short("u")
+                .long("unbounded")
+                .help("grow the tape when the pointer moves past either end"),
+        )
+        .arg(
+            Arg::with_name("overflow")
+                .short("o")
+                .long("overflow")
+                .value_name("POLICY")
+                .help("what a cell does when it goes past 0 or the cell's maximum")
+                .possible_values(&["error", "wrap", "saturate"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("width")
+                .short("w")
+                .long("width")
+                .value_name("BITS")
+                .help("number of bits in a cell")
+                .possible_values(&["8", "16", "32"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("eof")
+                .short("e")
+                .long("eof")
+                .value_name("POLICY")
+                .help("what a read does when the input is exhausted")
+                .possible_values(&["error", "zero", "minus-one", "unchanged"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("fuel")
+                .long("fuel")
+                .value_name("STEPS")
+                .help("maximum number of steps the machine may take")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("timeout")
+                .long("timeout")
+                .value_name("SECONDS")
+                .help("maximum number of seconds the machine may run")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("backend")
+                .short("b")
+                .long("backend")
+                .value_name("BACKEND")
+                .help("how the machine executes the program")
+                .possible_values(&["interpreter", "bytecode", "jit"])
+                .takes_value(true),
+        )
         .get_matches();
 
     let filename = matches.value_of("source").unwrap();
+    let config = config(&matches);
     let file = File::open(filename).expect("file to exist");
-    let mut reader = BufReader::new(file);
-    let mut source: Vec<u8> = Vec::new();
-
-    reader
-        .read_to_end(&mut source)
-        .expect("to be able to read file");
+    let mut commands = stream(file);
+    let mut diagnostics = vec![];
+    let (instructions, spans): (Vec<Command>, Vec<Span>) = iter::from_fn(|| loop {
+        match commands.next_spanned()? {
+            Ok(spanned) => return Some(spanned),
+            Err(problem) => diagnostics.push(commands.render(&problem)),
+        }
+    })
+    .unzip();
 
-    if let Ok(instructions) = parse(&source) {
-        if let Ok(_) = io_run(&instructions, io::stdin(), io::stdout()) {
-            // println!("Ran machine");
+    if !diagnostics.is_empty() {
+        for diagnostic in diagnostics {
+            eprint!("{}", diagnostic);
         }
+        process::exit(1);
+    }
+
+    if let Err(problem) = io_run(&instructions, io::stdin(), io::stdout(), config) {
+        let source = fs::read(filename).unwrap_or_default();
+        eprint!("{}", problem.locate(&spans).render(&source));
+        process::exit(1);
+    }
+}
+
+fn config(matches: &ArgMatches) -> MachineConfig {
+    let mut config = MachineConfig::default();
+    if let Some(tape) = matches.value_of("tape") {
+        config = config.tape(tape.parse().expect("tape to be a number"));
+    }
+    config = config.unbounded(matches.is_present("unbounded"));
+    match matches.value_of("overflow") {
+        Some("wrap") => config = config.overflow(Overflow::Wrap),
+        Some("saturate") => config = config.overflow(Overflow::Saturate),
+        _ => { /* keep erroring on overflow */ }
+    }
+    match matches.value_of("width") {
+        Some("16") => config = config.width(CellWidth::U16),
+        Some("32") => config = config.width(CellWidth::U32),
+        _ => { /* keep byte sized cells */ }
+    }
+    match matches.value_of("eof") {
+        Some("zero") => config = config.eof(Eof::Zero),
+        Some("minus-one") => config = config.eof(Eof::MinusOne),
+        Some("unchanged") => config = config.eof(Eof::Unchanged),
+        _ => { /* keep erroring at the end of input */ }
+    }
+    if let Some(fuel) = matches.value_of("fuel") {
+        config = config.fuel(fuel.parse().expect("fuel to be a number"));
+    }
+    if let Some(timeout) = matches.value_of("timeout") {
+        config = config.time_limit(Duration::from_secs_f64(
+            timeout.parse().expect("timeout to be a number of seconds"),
+        ));
+    }
+    match matches.value_of("backend") {
+        Some("bytecode") => config = config.backend(Backend::Bytecode),
+        Some("jit") => config = config.backend(Backend::Jit),
+        _ => { /* keep interpreting */ }
     }
+    config
 }
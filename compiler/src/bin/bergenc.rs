@@ -1,18 +1,19 @@
 extern crate bergen;
 extern crate clap;
 
-use bergen::brnfck::to_brnfck;
-use bergen::parser::parse;
-use clap::{App, Arg};
+use bergen::brnfck::{to_brnfck, to_c, CellWidth, Command, Eof, MachineConfig, Overflow};
+use bergen::parser::stream;
+use clap::{App, Arg, ArgMatches};
 use std::fs::File;
 use std::io;
-use std::io::{BufReader, Read};
+use std::iter;
+use std::process;
 
 fn main() {
     let matches = App::new("bergenc")
         .version("0.1.0")
         .author("Daan van Berkel")
-        .about("compiler for bergen language to brainf*ck")
+        .about("compiler for bergen language to brainf*ck or C")
         .arg(
             Arg::with_name("source")
                 .short("f")
@@ -21,18 +22,108 @@ fn main() {
                 .help("source for the bergen program to compile")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("target")
+                .long("target")
+                .value_name("LANGUAGE")
+                .help("language to compile the bergen program to")
+                .possible_values(&["brnfck", "c"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("tape")
+                .short("t")
+                .long("tape")
+                .value_name("CELLS")
+                .help("number of cells on the tape of the compiled program")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("unbounded")
+                .short("u")
+                .long("unbounded")
+                .help("grow the tape when the pointer moves past either end"),
+        )
+        .arg(
+            Arg::with_name("overflow")
+                .short("o")
+                .long("overflow")
+                .value_name("POLICY")
+                .help("what a cell does when it goes past 0 or the cell's maximum")
+                .possible_values(&["error", "wrap", "saturate"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("width")
+                .short("w")
+                .long("width")
+                .value_name("BITS")
+                .help("number of bits in a cell")
+                .possible_values(&["8", "16", "32"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("eof")
+                .short("e")
+                .long("eof")
+                .value_name("POLICY")
+                .help("what a read does when the input is exhausted")
+                .possible_values(&["error", "zero", "minus-one", "unchanged"])
+                .takes_value(true),
+        )
         .get_matches();
 
     let filename = matches.value_of("source").unwrap();
     let file = File::open(filename).expect("file to exist");
-    let mut reader = BufReader::new(file);
-    let mut source: Vec<u8> = Vec::new();
+    let mut commands = stream(file);
+    let mut diagnostics = vec![];
+    let instructions: Vec<Command> = iter::from_fn(|| loop {
+        match commands.next()? {
+            Ok(command) => return Some(command),
+            Err(problem) => diagnostics.push(commands.render(&problem)),
+        }
+    })
+    .collect();
+
+    if !diagnostics.is_empty() {
+        for diagnostic in diagnostics {
+            eprint!("{}", diagnostic);
+        }
+        process::exit(1);
+    }
 
-    reader
-        .read_to_end(&mut source)
-        .expect("to be able to read file");
+    match matches.value_of("target") {
+        Some("c") => {
+            if let Err(problem) = to_c(&instructions, config(&matches), io::stdout()) {
+                eprintln!("error: {}", problem);
+                process::exit(1);
+            }
+        }
+        _ => to_brnfck(instructions, io::stdout()).expect("to write to stdout"),
+    }
+}
 
-    if let Ok(instructions) = parse(&source) {
-        to_brnfck(&instructions, io::stdout()).expect("to write to stdout");
+fn config(matches: &ArgMatches) -> MachineConfig {
+    let mut config = MachineConfig::default();
+    if let Some(tape) = matches.value_of("tape") {
+        config = config.tape(tape.parse().expect("tape to be a number"));
+    }
+    config = config.unbounded(matches.is_present("unbounded"));
+    match matches.value_of("overflow") {
+        Some("wrap") => config = config.overflow(Overflow::Wrap),
+        Some("saturate") => config = config.overflow(Overflow::Saturate),
+        _ => { /* keep erroring on overflow */ }
+    }
+    match matches.value_of("width") {
+        Some("16") => config = config.width(CellWidth::U16),
+        Some("32") => config = config.width(CellWidth::U32),
+        _ => { /* keep byte sized cells */ }
+    }
+    match matches.value_of("eof") {
+        Some("zero") => config = config.eof(Eof::Zero),
+        Some("minus-one") => config = config.eof(Eof::MinusOne),
+        Some("unchanged") => config = config.eof(Eof::Unchanged),
+        _ => { /* keep erroring at the end of input */ }
     }
+    config
 }
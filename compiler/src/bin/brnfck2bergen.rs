@@ -1,12 +1,14 @@
 extern crate bergen;
 extern crate clap;
 
-use bergen::brnfck::to_bergen;
 use bergen::brnfck::parser::parse;
-use clap::{App, Arg};
+use bergen::brnfck::{to_bergen, Spacing};
+use bergen::Error;
+use clap::{App, Arg, ArgMatches};
 use std::fs::File;
 use std::io;
 use std::io::{BufReader, Read};
+use std::process;
 
 fn main() {
     let matches = App::new("brnfck2bergen")
@@ -21,19 +23,48 @@ fn main() {
                 .help("source for the brnfck program to transpile")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("gap")
+                .short("g")
+                .long("gap")
+                .value_name("COLUMNS")
+                .help("number of columns between a loop body and its brackets")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("plains")
+                .short("p")
+                .long("plains")
+                .help("draw gaps as plains instead of valleys"),
+        )
         .get_matches();
 
     let filename = matches.value_of("source").unwrap();
-    let file = File::open(filename).expect("file to exist");
+    let spacing = spacing(&matches);
+
+    if let Err(problem) = transpile(filename, spacing) {
+        eprintln!("error: {}", problem);
+        process::exit(1);
+    }
+}
+
+fn transpile(filename: &str, spacing: Spacing) -> Result<(), Error> {
+    let file = File::open(filename)?;
     let mut reader = BufReader::new(file);
     let mut source: Vec<u8> = Vec::new();
 
-    reader
-        .read_to_end(&mut source)
-        .expect("to be able to read file");
+    reader.read_to_end(&mut source)?;
+    let instructions = parse(&source)?;
+    to_bergen(&instructions, spacing, io::stdout())?;
+    Ok(())
+}
 
-    if let Ok(instructions) = parse(&source) {
-        to_bergen(&instructions, io::stdout()).expect("to be able to write bergen");
+fn spacing(matches: &ArgMatches) -> Spacing {
+    Spacing {
+        loops: matches
+            .value_of("gap")
+            .map(|gap| gap.parse().expect("gap to be a number"))
+            .unwrap_or(0),
+        plains: matches.is_present("plains"),
     }
 }
-
@@ -1,8 +1,8 @@
 extern crate bergen;
 extern crate clap;
 
-use bergen::brnfck::{program_from, to_bergen};
-use clap::{App, Arg};
+use bergen::brnfck::{program_from, to_bergen, Spacing};
+use clap::{App, Arg, ArgMatches};
 use std::io;
 
 fn main() {
@@ -18,12 +18,36 @@ fn main() {
                 .help("create a bergen program that outputs a sentence")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("gap")
+                .short("g")
+                .long("gap")
+                .value_name("COLUMNS")
+                .help("number of columns between a loop body and its brackets")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("plains")
+                .short("p")
+                .long("plains")
+                .help("draw gaps as plains instead of valleys"),
+        )
         .get_matches();
 
     let sentence = matches.value_of("sentence").unwrap();
+    let spacing = spacing(&matches);
     let characters = sentence.as_bytes();
 
-    let program = program_from(&characters);
-    to_bergen(&program, io::stdout()).expect("to write program");
+    let program = program_from(characters);
+    to_bergen(&program, spacing, io::stdout()).expect("to write program");
 }
 
+fn spacing(matches: &ArgMatches) -> Spacing {
+    Spacing {
+        loops: matches
+            .value_of("gap")
+            .map(|gap| gap.parse().expect("gap to be a number"))
+            .unwrap_or(0),
+        plains: matches.is_present("plains"),
+    }
+}
@@ -0,0 +1,18 @@
+extern crate bergen;
+extern crate clap;
+
+use bergen::parser::Automaton;
+use clap::App;
+use std::io;
+
+fn main() {
+    App::new("states")
+        .version("0.1.0")
+        .author("Daan van Berkel")
+        .about("write the states of the bergen parser as a graphviz digraph")
+        .get_matches();
+
+    Automaton::new()
+        .to_dot(io::stdout())
+        .expect("to write states");
+}
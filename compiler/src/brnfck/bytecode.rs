@@ -0,0 +1,43 @@
+use super::machine::{Instruction, Operation};
+use std::convert::TryFrom;
+
+pub const WIDTH: usize = 2;
+
+pub const INCREMENT: u32 = 0;
+pub const DECREMENT: u32 = 1;
+pub const RIGHT: u32 = 2;
+pub const LEFT: u32 = 3;
+pub const OPEN: u32 = 4;
+pub const CLOSE: u32 = 5;
+pub const STEP: u32 = 6;
+
+pub fn compile(program: &[Instruction]) -> Vec<u32> {
+    let mut code = Vec::with_capacity(WIDTH * program.len());
+    for instruction in program {
+        let (opcode, operand) = match instruction.operation {
+            Operation::Add(amount) if amount > 0 => counted(INCREMENT, amount.unsigned_abs()),
+            Operation::Add(amount) => counted(DECREMENT, amount.unsigned_abs()),
+            Operation::Move(distance) if distance > 0 => {
+                counted(RIGHT, distance.unsigned_abs() as u64)
+            }
+            Operation::Move(distance) => counted(LEFT, distance.unsigned_abs() as u64),
+            Operation::JumpAhead(closing) => (OPEN, jump(closing + 1)),
+            Operation::JumpBack(opening) => (CLOSE, jump(opening + 1)),
+            _ => (STEP, 0),
+        };
+        code.push(opcode);
+        code.push(operand);
+    }
+    code
+}
+
+fn counted(opcode: u32, count: u64) -> (u32, u32) {
+    match u32::try_from(count) {
+        Ok(count) => (opcode, count),
+        Err(_) => (STEP, 0),
+    }
+}
+
+fn jump(counter: usize) -> u32 {
+    (WIDTH * counter) as u32
+}
@@ -0,0 +1,266 @@
+use super::config::{CellWidth, Eof, MachineConfig, Overflow};
+use super::error::{MachineError, MachineErrorKind, Position};
+use super::ir::{self, Node, Op};
+use super::machine::Command;
+use crate::Error;
+use std::io::{self, BufWriter, Write};
+
+const LIMIT: u64 = 1 << 32;
+
+const HEADER: &str = "#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+";
+
+const FAIL: &str = "static inline void fail(const char *message) {
+    fprintf(stderr, \"error: %s\\n\", message);
+    exit(1);
+}
+";
+
+const GROW_RIGHT: &str = "    while (n >= length - p) {
+        tape = realloc(tape, 2 * length * sizeof(cell));
+        if (!tape) fail(\"the tape could not grow\");
+        memset(tape + length, 0, length * sizeof(cell));
+        length *= 2;
+    }
+";
+
+const GROW_LEFT: &str = "    while (n > p) {
+        tape = realloc(tape, 2 * length * sizeof(cell));
+        if (!tape) fail(\"the tape could not grow\");
+        memmove(tape + length, tape, length * sizeof(cell));
+        memset(tape, 0, length * sizeof(cell));
+        p += length;
+        length *= 2;
+    }
+";
+
+const START: &str = "int main(void) {
+    tape = calloc(length, sizeof(cell));
+    if (!tape) fail(\"the tape could not be allocated\");
+";
+
+const FINISH: &str = "    return 0;
+}
+";
+
+pub fn to_c<O: Write>(
+    instructions: &[Command],
+    config: MachineConfig,
+    output: O,
+) -> Result<(), Error> {
+    if config.tape == 0 {
+        return Err(MachineError::new(MachineErrorKind::EmptyTape, Position::default()).into());
+    }
+    let nodes = ir::lower(instructions)?;
+    let mut output = BufWriter::new(output);
+    prelude(&mut output, config)?;
+    write!(output, "{}", START)?;
+    body(&mut output, &nodes, config.overflow, 1)?;
+    write!(output, "{}", FINISH)?;
+    output.flush()?;
+    Ok(())
+}
+
+fn prelude<O: Write>(output: &mut O, config: MachineConfig) -> Result<(), io::Error> {
+    let cell = match config.width {
+        CellWidth::U8 => "uint8_t",
+        CellWidth::U16 => "uint16_t",
+        CellWidth::U32 => "uint32_t",
+    };
+    writeln!(output, "{}", HEADER)?;
+    writeln!(output, "typedef {} cell;\n", cell)?;
+    writeln!(output, "static size_t length = {};", config.tape)?;
+    writeln!(output, "static cell *tape;")?;
+    writeln!(output, "static size_t p;\n")?;
+    writeln!(output, "{}", FAIL)?;
+    writeln!(output, "static inline void right(size_t n) {{")?;
+    if config.unbounded {
+        write!(output, "{}", GROW_RIGHT)?;
+    } else {
+        let problem = MachineErrorKind::PointerIncrementOutOfBound.describe();
+        writeln!(output, "    if (n >= length - p) fail(\"{}\");", problem)?;
+    }
+    writeln!(output, "    p += n;\n}}\n")?;
+    writeln!(output, "static inline void left(size_t n) {{")?;
+    if config.unbounded {
+        write!(output, "{}", GROW_LEFT)?;
+    } else {
+        let problem = MachineErrorKind::PointerDecrementOutOfBound.describe();
+        writeln!(output, "    if (n > p) fail(\"{}\");", problem)?;
+    }
+    writeln!(output, "    p -= n;\n}}\n")?;
+    writeln!(output, "static inline void input(void) {{")?;
+    writeln!(output, "    int c = getchar();")?;
+    writeln!(output, "    if (c != EOF) {{")?;
+    writeln!(output, "        tape[p] = (cell) c;")?;
+    writeln!(output, "        return;")?;
+    writeln!(output, "    }}")?;
+    match config.eof {
+        Eof::Error => {
+            let problem = MachineErrorKind::NoByteRead.describe();
+            writeln!(output, "    fail(\"{}\");", problem)?
+        }
+        Eof::Zero => writeln!(output, "    tape[p] = 0;")?,
+        Eof::MinusOne => writeln!(output, "    tape[p] = (cell) -1;")?,
+        Eof::Unchanged => { /* leave the cell as it is */ }
+    }
+    writeln!(output, "}}\n")?;
+    let (add, sub) = match config.overflow {
+        Overflow::Error => (
+            format!(
+                "    if (n > (uint64_t) (cell) -1 - tape[p]) fail(\"{}\");\n    tape[p] += n;",
+                MachineErrorKind::CellOverflow.describe()
+            ),
+            format!(
+                "    if (n > tape[p]) fail(\"{}\");\n    tape[p] -= n;",
+                MachineErrorKind::CellUnderflow.describe()
+            ),
+        ),
+        Overflow::Wrap => (
+            "    tape[p] += n;".to_string(),
+            "    tape[p] -= n;".to_string(),
+        ),
+        Overflow::Saturate => (
+            "    tape[p] = n > (uint64_t) (cell) -1 - tape[p] ? (cell) -1 : tape[p] + n;"
+                .to_string(),
+            "    tape[p] = n > tape[p] ? 0 : tape[p] - n;".to_string(),
+        ),
+    };
+    writeln!(
+        output,
+        "static inline void add(uint64_t n) {{\n{}\n}}\n",
+        add
+    )?;
+    writeln!(
+        output,
+        "static inline void sub(uint64_t n) {{\n{}\n}}\n",
+        sub
+    )
+}
+
+fn body<O: Write>(
+    output: &mut O,
+    nodes: &[Node],
+    overflow: Overflow,
+    depth: usize,
+) -> Result<(), io::Error> {
+    let indent = "    ".repeat(depth);
+    for node in nodes {
+        match node.op {
+            Op::Add(amount) => {
+                let (operator, amount) = assign(amount, overflow);
+                writeln!(output, "{}{}({});", indent, operator, amount)?
+            }
+            Op::Move(distance) if distance > 0 => {
+                writeln!(output, "{}right({});", indent, distance)?
+            }
+            Op::Move(distance) => writeln!(output, "{}left({});", indent, -distance)?,
+            Op::Read => writeln!(output, "{}input();", indent)?,
+            Op::Write => writeln!(output, "{}putchar(tape[p]);", indent)?,
+            Op::Loop(ref nodes) => match (ir::idiom(nodes, overflow), ir::balance(nodes)) {
+                (Some(Op::Scan(stride)), _) if stride > 0 => {
+                    writeln!(output, "{}while (tape[p]) right({});", indent, stride)?
+                }
+                (Some(Op::Scan(stride)), _) => {
+                    writeln!(output, "{}while (tape[p]) left({});", indent, -stride)?
+                }
+                (Some(Op::Clear), _) => writeln!(output, "{}tape[p] = 0;", indent)?,
+                (Some(Op::MulAdd(ref factors)), Some((step, _))) if overflow != Overflow::Error => {
+                    multiply(output, &indent, step, factors, overflow)?
+                }
+                _ => {
+                    writeln!(output, "{}while (tape[p]) {{", indent)?;
+                    body(output, nodes, overflow, depth + 1)?;
+                    writeln!(output, "{}}}", indent)?;
+                }
+            },
+            Op::Clear | Op::MulAdd(_) | Op::Scan(_) => unreachable!(),
+        }
+    }
+    Ok(())
+}
+
+fn multiply<O: Write>(
+    output: &mut O,
+    indent: &str,
+    step: i64,
+    factors: &[(isize, i64)],
+    overflow: Overflow,
+) -> Result<(), io::Error> {
+    writeln!(output, "{}if (tape[p]) {{", indent)?;
+    if step < 0 {
+        writeln!(output, "{}    uint64_t count = tape[p];", indent)?;
+    } else {
+        writeln!(output, "{}    uint64_t count = (cell) -tape[p];", indent)?;
+    }
+    let mut offset = 0;
+    for &(target, factor) in factors {
+        shift(output, indent, target - offset)?;
+        offset = target;
+        let (operator, factor) = assign(factor, overflow);
+        writeln!(output, "{}    {}({} * count);", indent, operator, factor)?;
+    }
+    shift(output, indent, -offset)?;
+    writeln!(output, "{}    tape[p] = 0;", indent)?;
+    writeln!(output, "{}}}", indent)
+}
+
+fn assign(amount: i64, overflow: Overflow) -> (&'static str, u64) {
+    let operator = if amount > 0 { "add" } else { "sub" };
+    let amount = match overflow {
+        Overflow::Wrap => amount.unsigned_abs() % LIMIT,
+        _ => amount.unsigned_abs().min(LIMIT),
+    };
+    (operator, amount)
+}
+
+fn shift<O: Write>(output: &mut O, indent: &str, distance: isize) -> Result<(), io::Error> {
+    if distance > 0 {
+        writeln!(output, "{}    right({});", indent, distance)?;
+    } else if distance < 0 {
+        writeln!(output, "{}    left({});", indent, -distance)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parser::parse;
+    use super::*;
+
+    fn emit(source: &[u8], config: MachineConfig) -> String {
+        let mut output: Vec<u8> = vec![];
+        to_c(&parse(source).unwrap(), config, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn should_emit_optimised_program() {
+        let config = MachineConfig::default().overflow(Overflow::Wrap);
+        let program = emit(b"+++[->++<]>.,[-]", config);
+        let main = &program[program.find("int main").unwrap()..];
+
+        assert_eq!(
+            main,
+            "int main(void) {\n    tape = calloc(length, sizeof(cell));\n    if (!tape) fail(\"the tape could not be allocated\");\n    add(3);\n    if (tape[p]) {\n        uint64_t count = tape[p];\n        right(1);\n        add(2 * count);\n        left(1);\n        tape[p] = 0;\n    }\n    right(1);\n    putchar(tape[p]);\n    input();\n    tape[p] = 0;\n    return 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn should_honour_tape_width_overflow_and_eof() {
+        let config = MachineConfig::default()
+            .tape(64)
+            .width(CellWidth::U16)
+            .eof(Eof::MinusOne);
+        let program = emit(b",.", config);
+
+        assert!(program.contains("typedef uint16_t cell;"));
+        assert!(program.contains("static size_t length = 64;"));
+        assert!(program.contains("tape[p] = (cell) -1;"));
+        assert!(program.contains("fail(\"pointer moved past the last cell\")"));
+        assert!(program.contains("fail(\"cell overflowed\")"));
+    }
+}
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+pub const TAPE: usize = 30_000;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MachineConfig {
+    pub tape: usize,
+    pub unbounded: bool,
+    pub overflow: Overflow,
+    pub width: CellWidth,
+    pub eof: Eof,
+    pub fuel: Option<u64>,
+    pub time_limit: Option<Duration>,
+    pub backend: Backend,
+}
+
+impl MachineConfig {
+    pub fn tape(self, tape: usize) -> MachineConfig {
+        MachineConfig { tape, ..self }
+    }
+
+    pub fn unbounded(self, unbounded: bool) -> MachineConfig {
+        MachineConfig { unbounded, ..self }
+    }
+
+    pub fn overflow(self, overflow: Overflow) -> MachineConfig {
+        MachineConfig { overflow, ..self }
+    }
+
+    pub fn width(self, width: CellWidth) -> MachineConfig {
+        MachineConfig { width, ..self }
+    }
+
+    pub fn eof(self, eof: Eof) -> MachineConfig {
+        MachineConfig { eof, ..self }
+    }
+
+    pub fn fuel(self, steps: u64) -> MachineConfig {
+        MachineConfig {
+            fuel: Some(steps),
+            ..self
+        }
+    }
+
+    pub fn time_limit(self, limit: Duration) -> MachineConfig {
+        MachineConfig {
+            time_limit: Some(limit),
+            ..self
+        }
+    }
+
+    pub fn backend(self, backend: Backend) -> MachineConfig {
+        MachineConfig { backend, ..self }
+    }
+}
+
+impl Default for MachineConfig {
+    fn default() -> Self {
+        MachineConfig {
+            tape: TAPE,
+            unbounded: false,
+            overflow: Overflow::Error,
+            width: CellWidth::U8,
+            eof: Eof::Error,
+            fuel: None,
+            time_limit: None,
+            backend: Backend::Interpreter,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Overflow {
+    Error,
+    Wrap,
+    Saturate,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Eof {
+    Error,
+    Zero,
+    MinusOne,
+    Unchanged,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CellWidth {
+    U8,
+    U16,
+    U32,
+}
+
+impl CellWidth {
+    pub fn max(self) -> u32 {
+        match self {
+            CellWidth::U8 => u32::from(u8::MAX),
+            CellWidth::U16 => u32::from(u16::MAX),
+            CellWidth::U32 => u32::MAX,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Backend {
+    Interpreter,
+    Bytecode,
+    Jit,
+}
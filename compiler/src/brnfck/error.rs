@@ -0,0 +1,154 @@
+use crate::parser::{self, Span};
+use std::error;
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Position {
+    pub instruction_pointer: usize,
+    pub cell_pointer: isize,
+    pub value: u32,
+    pub steps: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MachineError {
+    pub kind: MachineErrorKind,
+    pub position: Position,
+    pub span: Option<Span>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MachineErrorKind {
+    EmptyTape,
+    PointerIncrementOutOfBound,
+    PointerDecrementOutOfBound,
+    CellOverflow,
+    CellUnderflow,
+    UnmatchedJumpAhead,
+    UnmatchedJumpBack,
+    NoInput,
+    InputError,
+    NoByteRead,
+    NoOutput,
+    OutputError,
+    NoByteWritten,
+    OutOfFuel,
+    TimedOut,
+}
+
+impl MachineError {
+    pub fn new(kind: MachineErrorKind, position: Position) -> MachineError {
+        MachineError {
+            kind,
+            position,
+            span: None,
+        }
+    }
+
+    pub fn locate(self, spans: &[Span]) -> MachineError {
+        MachineError {
+            span: spans.get(self.position.instruction_pointer).cloned(),
+            ..self
+        }
+    }
+
+    pub fn render(&self, source: &[u8]) -> String {
+        match self.span {
+            Some(ref span) => parser::render(source, span, &self.message(), &self.help()),
+            None => {
+                let mut output = format!("error: {}\n", self.message());
+                for line in self.help() {
+                    output += &format!("  = help: {}\n", line);
+                }
+                output
+            }
+        }
+    }
+
+    fn message(&self) -> String {
+        format!(
+            "{} at step {}",
+            self.kind.describe(),
+            self.position.steps + 1
+        )
+    }
+
+    fn help(&self) -> Vec<String> {
+        vec![format!(
+            "instruction {} was running with cell {} holding {}",
+            self.position.instruction_pointer + 1,
+            self.position.cell_pointer,
+            self.position.value
+        )]
+    }
+}
+
+impl MachineErrorKind {
+    pub fn describe(&self) -> &str {
+        match self {
+            MachineErrorKind::EmptyTape => "the tape has no cells",
+            MachineErrorKind::PointerIncrementOutOfBound => "pointer moved past the last cell",
+            MachineErrorKind::PointerDecrementOutOfBound => "pointer moved before the first cell",
+            MachineErrorKind::CellOverflow => "cell overflowed",
+            MachineErrorKind::CellUnderflow => "cell underflowed",
+            MachineErrorKind::UnmatchedJumpAhead => "unmatched `[`",
+            MachineErrorKind::UnmatchedJumpBack => "unmatched `]`",
+            MachineErrorKind::NoInput => "there is no input to read from",
+            MachineErrorKind::InputError => "input could not be read",
+            MachineErrorKind::NoByteRead => "input is exhausted",
+            MachineErrorKind::NoOutput => "there is no output to write to",
+            MachineErrorKind::OutputError => "output could not be written",
+            MachineErrorKind::NoByteWritten => "no byte was written",
+            MachineErrorKind::OutOfFuel => "ran out of fuel",
+            MachineErrorKind::TimedOut => "ran out of time",
+        }
+    }
+}
+
+impl Display for MachineError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl error::Error for MachineError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_render_error_at_span_of_failing_mountain() {
+        let source: &[u8] = "    \n /\\/\\ \n/    \\\n".as_bytes();
+        let spans = [Span {
+            band: 0,
+            line: 0,
+            rows: 0..3,
+            columns: 0..6,
+        }];
+        let error = MachineError::new(MachineErrorKind::CellUnderflow, Position::default());
+
+        assert_eq!(
+            error.locate(&spans).render(source),
+            "error: cell underflowed at step 1\n --> band 1, line 1, columns 1-6\n  |\n1 |     \n2 |  /\\/\\ \n3 | /    \\\n  | ^^^^^^\n  = help: instruction 1 was running with cell 0 holding 0\n"
+        );
+    }
+
+    #[test]
+    fn should_render_error_without_span() {
+        let error = MachineError::new(
+            MachineErrorKind::NoByteRead,
+            Position {
+                instruction_pointer: 3,
+                cell_pointer: -2,
+                value: 7,
+                steps: 9,
+            },
+        );
+
+        assert_eq!(
+            error.render(&[]),
+            "error: input is exhausted at step 10\n  = help: instruction 4 was running with cell -2 holding 7\n"
+        );
+    }
+}
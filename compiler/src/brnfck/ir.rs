@@ -0,0 +1,303 @@
+use super::config::Overflow;
+use super::error::{MachineError, MachineErrorKind, Position};
+use super::machine::Command;
+use std::mem;
+use std::ops::Range;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Op {
+    Add(i64),
+    Move(isize),
+    Loop(Vec<Node>),
+    Read,
+    Write,
+    Clear,
+    MulAdd(Vec<(isize, i64)>),
+    Scan(isize),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Node {
+    pub op: Op,
+    pub source: Range<usize>,
+}
+
+impl Node {
+    fn new(op: Op, index: usize) -> Node {
+        Node {
+            op,
+            source: index..index + 1,
+        }
+    }
+}
+
+pub fn lower(instructions: &[Command]) -> Result<Vec<Node>, MachineError> {
+    let mut nodes = vec![];
+    let mut openings: Vec<(usize, Vec<Node>)> = vec![];
+    for (index, instruction) in instructions.iter().enumerate() {
+        let op = match instruction {
+            Command::Increment => Op::Add(1),
+            Command::Decrement => Op::Add(-1),
+            Command::IncrementPointer => Op::Move(1),
+            Command::DecrementPointer => Op::Move(-1),
+            Command::Read => Op::Read,
+            Command::Write => Op::Write,
+            Command::JumpAhead => {
+                openings.push((index, mem::take(&mut nodes)));
+                continue;
+            }
+            Command::JumpBack => {
+                let (opening, outer) = openings
+                    .pop()
+                    .ok_or_else(|| unmatched(MachineErrorKind::UnmatchedJumpBack, index))?;
+                let body = mem::replace(&mut nodes, outer);
+                nodes.push(Node {
+                    op: Op::Loop(body),
+                    source: opening..index + 1,
+                });
+                continue;
+            }
+        };
+        fold(&mut nodes, Node::new(op, index));
+    }
+    match openings.first() {
+        Some((opening, _)) => Err(unmatched(MachineErrorKind::UnmatchedJumpAhead, *opening)),
+        None => Ok(nodes),
+    }
+}
+
+fn fold(nodes: &mut Vec<Node>, node: Node) {
+    if let Some(last) = nodes.last_mut() {
+        let folded = match (&mut last.op, &node.op) {
+            (Op::Add(total), Op::Add(amount)) if total.signum() == amount.signum() => {
+                *total += amount;
+                true
+            }
+            (Op::Move(total), Op::Move(distance)) if total.signum() == distance.signum() => {
+                *total += distance;
+                true
+            }
+            _ => false,
+        };
+        if folded {
+            last.source.end = node.source.end;
+            return;
+        }
+    }
+    nodes.push(node);
+}
+
+pub fn optimise(nodes: Vec<Node>, overflow: Overflow) -> Vec<Node> {
+    nodes
+        .into_iter()
+        .map(|node| match node.op {
+            Op::Loop(body) => {
+                let body = optimise(body, overflow);
+                Node {
+                    op: idiom(&body, overflow).unwrap_or(Op::Loop(body)),
+                    source: node.source,
+                }
+            }
+            _ => node,
+        })
+        .collect()
+}
+
+pub fn idiom(body: &[Node], overflow: Overflow) -> Option<Op> {
+    if let [Node {
+        op: Op::Move(stride),
+        ..
+    }] = body
+    {
+        return Some(Op::Scan(*stride));
+    }
+    match balance(body) {
+        Some((step, factors)) if step < 0 || overflow == Overflow::Wrap => {
+            if factors.is_empty() {
+                Some(Op::Clear)
+            } else {
+                Some(Op::MulAdd(factors))
+            }
+        }
+        _ => None,
+    }
+}
+
+pub fn balance(body: &[Node]) -> Option<(i64, Vec<(isize, i64)>)> {
+    let mut offset = 0;
+    let mut reach = (0, 0);
+    let mut step = None;
+    let mut factors: Vec<(isize, i64)> = vec![];
+    for node in body {
+        match node.op {
+            Op::Move(distance) => {
+                offset += distance;
+                reach = (reach.0.min(offset), reach.1.max(offset));
+            }
+            Op::Add(amount) if offset == 0 && step.is_none() && amount.abs() == 1 => {
+                step = Some(amount)
+            }
+            Op::Add(amount) if offset != 0 && factors.iter().all(|(seen, _)| *seen != offset) => {
+                factors.push((offset, amount))
+            }
+            _ => return None,
+        }
+    }
+    let touched = |extreme| extreme == 0 || factors.iter().any(|(seen, _)| *seen == extreme);
+    if offset != 0 || !touched(reach.0) || !touched(reach.1) {
+        return None;
+    }
+    step.map(|step| (step, factors))
+}
+
+fn unmatched(kind: MachineErrorKind, instruction_pointer: usize) -> MachineError {
+    MachineError::new(
+        kind,
+        Position {
+            instruction_pointer,
+            ..Position::default()
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parser::parse;
+    use super::*;
+
+    #[test]
+    fn should_fold_runs_of_the_same_command() {
+        let instructions = parse(b"+++-->>.<,").unwrap();
+
+        assert_eq!(
+            lower(&instructions),
+            Ok(vec![
+                Node {
+                    op: Op::Add(3),
+                    source: 0..3,
+                },
+                Node {
+                    op: Op::Add(-2),
+                    source: 3..5,
+                },
+                Node {
+                    op: Op::Move(2),
+                    source: 5..7,
+                },
+                Node {
+                    op: Op::Write,
+                    source: 7..8,
+                },
+                Node {
+                    op: Op::Move(-1),
+                    source: 8..9,
+                },
+                Node {
+                    op: Op::Read,
+                    source: 9..10,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn should_nest_loops_without_folding_across_them() {
+        let instructions = parse(b"+[[-]+]+").unwrap();
+
+        assert_eq!(
+            lower(&instructions),
+            Ok(vec![
+                Node {
+                    op: Op::Add(1),
+                    source: 0..1,
+                },
+                Node {
+                    op: Op::Loop(vec![
+                        Node {
+                            op: Op::Loop(vec![Node {
+                                op: Op::Add(-1),
+                                source: 3..4,
+                            }]),
+                            source: 2..5,
+                        },
+                        Node {
+                            op: Op::Add(1),
+                            source: 5..6,
+                        },
+                    ]),
+                    source: 1..7,
+                },
+                Node {
+                    op: Op::Add(1),
+                    source: 7..8,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn should_recognise_clear_multiply_and_scan_loops() {
+        for (source, op) in [
+            (&b"[-]"[..], Op::Clear),
+            (&b"[->+>++<<]"[..], Op::MulAdd(vec![(1, 1), (2, 2)])),
+            (&b"[>>-<<-]"[..], Op::MulAdd(vec![(2, -1)])),
+            (&b"[<<<]"[..], Op::Scan(-3)),
+        ] {
+            let nodes = lower(&parse(source).unwrap()).unwrap();
+
+            assert_eq!(
+                optimise(nodes, Overflow::Error),
+                vec![Node {
+                    op,
+                    source: 0..source.len(),
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn should_only_recognise_counting_up_when_cells_wrap() {
+        let nodes = lower(&parse(b"[+>-<]").unwrap()).unwrap();
+
+        assert_eq!(
+            optimise(nodes.clone(), Overflow::Wrap)[0].op,
+            Op::MulAdd(vec![(1, -1)])
+        );
+        assert_eq!(optimise(nodes.clone(), Overflow::Saturate), nodes);
+    }
+
+    #[test]
+    fn should_leave_unbalanced_loops_alone() {
+        for source in [
+            &b"[->+]"[..],
+            &b"[--]"[..],
+            &b"[>+<>+<-]"[..],
+            &b"[-<>]"[..],
+            &b"[-.]"[..],
+        ] {
+            let nodes = lower(&parse(source).unwrap()).unwrap();
+
+            assert_eq!(optimise(nodes.clone(), Overflow::Wrap), nodes);
+        }
+    }
+
+    #[test]
+    fn should_report_outermost_unmatched_jump() {
+        let (ahead, back) = (Command::JumpAhead, Command::JumpBack);
+
+        for (instructions, kind, instruction_pointer) in [
+            (
+                [ahead, ahead, back],
+                MachineErrorKind::UnmatchedJumpAhead,
+                0,
+            ),
+            ([ahead, back, back], MachineErrorKind::UnmatchedJumpBack, 2),
+        ] {
+            assert_eq!(
+                lower(&instructions)
+                    .map_err(|error| (error.kind, error.position.instruction_pointer)),
+                Err((kind, instruction_pointer))
+            );
+        }
+    }
+}
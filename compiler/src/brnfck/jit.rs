@@ -0,0 +1,355 @@
+use super::machine::{Instruction, Operation};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ffi::c_void;
+use std::mem;
+use std::ptr;
+
+const CELLS: u8 = 0;
+const LENGTH: u8 = 8;
+const POINTER: u8 = 16;
+const STEPS: u8 = 24;
+const FUEL: u8 = 32;
+const MAX: u8 = 40;
+const TICKS: u8 = 48;
+const CALLBACK: u8 = 64;
+const CLOCK: u8 = 72;
+
+const RBX: u8 = 3;
+const R12: u8 = 12;
+const R13: u8 = 13;
+const R14: u8 = 14;
+
+pub type Callback = extern "sysv64" fn(*mut State, u64) -> u64;
+
+pub const FAILED: u64 = u64::MAX;
+
+#[repr(C)]
+pub struct State {
+    pub cells: *mut u32,
+    pub length: u64,
+    pub pointer: u64,
+    pub steps: u64,
+    pub fuel: u64,
+    pub max: u64,
+    pub ticks: u64,
+    pub context: *mut c_void,
+    pub callback: Callback,
+    pub clock: Callback,
+}
+
+pub struct Jit {
+    code: *mut u8,
+    size: usize,
+    labels: Vec<usize>,
+}
+
+impl Jit {
+    pub fn compile(program: &[Instruction], fuel: bool, clock: bool) -> Option<Jit> {
+        i32::try_from(program.len()).ok()?;
+        let mut assembler = Assembler::new();
+        assembler.prologue();
+        for (counter, instruction) in program.iter().enumerate() {
+            assembler.instruction(counter, instruction.operation, fuel, clock);
+        }
+        assembler.halt(program.len());
+        for (counter, instruction) in program.iter().enumerate() {
+            assembler.slow(
+                counter,
+                &successors(program, counter, instruction.operation),
+            );
+            if clock {
+                if let Operation::JumpBack(opening) = instruction.operation {
+                    assembler.clock(counter, opening + 1);
+                }
+            }
+        }
+        assembler.epilogue();
+        let (bytes, labels) = assembler.finish(program.len());
+        Jit::load(&bytes, labels)
+    }
+
+    fn load(bytes: &[u8], labels: Vec<usize>) -> Option<Jit> {
+        let size = bytes.len();
+        unsafe {
+            let code = libc::mmap(
+                ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            );
+            if code == libc::MAP_FAILED {
+                return None;
+            }
+            ptr::copy_nonoverlapping(bytes.as_ptr(), code as *mut u8, size);
+            if libc::mprotect(code, size, libc::PROT_READ | libc::PROT_EXEC) != 0 {
+                libc::munmap(code, size);
+                return None;
+            }
+            Some(Jit {
+                code: code as *mut u8,
+                size,
+                labels,
+            })
+        }
+    }
+
+    pub fn run(&self, state: &mut State, counter: usize) -> bool {
+        unsafe {
+            let function: extern "sysv64" fn(*mut State, *const u8) -> u64 =
+                mem::transmute(self.code);
+            function(state, self.code.add(self.labels[counter])) == 0
+        }
+    }
+}
+
+impl Drop for Jit {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.code as *mut c_void, self.size);
+        }
+    }
+}
+
+fn successors(program: &[Instruction], counter: usize, operation: Operation) -> Vec<usize> {
+    let next = counter + 1;
+    match operation {
+        Operation::JumpAhead(closing) => vec![next, closing + 1],
+        Operation::JumpBack(opening) => vec![next, opening + 1],
+        Operation::MulAdd { .. } | Operation::Scan(_) => match program[next].operation {
+            Operation::JumpAhead(closing) => vec![next, closing + 1],
+            _ => vec![next],
+        },
+        _ => vec![next],
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+enum Label {
+    Instruction(usize),
+    Slow(usize),
+    Clock(usize),
+    Failed,
+    Epilogue,
+}
+
+struct Assembler {
+    code: Vec<u8>,
+    labels: HashMap<Label, usize>,
+    fixups: Vec<(usize, Label)>,
+}
+
+impl Assembler {
+    fn new() -> Assembler {
+        Assembler {
+            code: vec![],
+            labels: HashMap::new(),
+            fixups: vec![],
+        }
+    }
+
+    fn finish(mut self, length: usize) -> (Vec<u8>, Vec<usize>) {
+        for (position, target) in &self.fixups {
+            let relative = self.labels[target] as i64 - (*position as i64 + 4);
+            self.code[*position..*position + 4].copy_from_slice(&(relative as i32).to_le_bytes());
+        }
+        let offsets = (0..=length)
+            .map(|counter| self.labels[&Label::Instruction(counter)])
+            .collect();
+        (self.code, offsets)
+    }
+
+    fn label(&mut self, label: Label) {
+        self.labels.insert(label, self.code.len());
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.code.extend_from_slice(bytes);
+    }
+
+    fn immediate(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn jump(&mut self, opcode: &[u8], target: Label) {
+        self.bytes(opcode);
+        self.fixups.push((self.code.len(), target));
+        self.immediate(0);
+    }
+
+    fn load(&mut self, register: u8, displacement: u8) {
+        self.state(0x8B, register, displacement);
+    }
+
+    fn store(&mut self, displacement: u8, register: u8) {
+        self.state(0x89, register, displacement);
+    }
+
+    fn state(&mut self, opcode: u8, register: u8, displacement: u8) {
+        let rex = 0x49 | ((register >> 3) << 2);
+        let modrm = 0x47 | ((register & 7) << 3);
+        self.bytes(&[rex, opcode, modrm, displacement]);
+    }
+
+    fn prologue(&mut self) {
+        self.bytes(&[0x53, 0x55, 0x41, 0x54, 0x41, 0x55, 0x41, 0x56, 0x41, 0x57]);
+        self.bytes(&[0x48, 0x83, 0xEC, 0x08]);
+        self.bytes(&[0x49, 0x89, 0xFF]);
+        self.load(RBX, CELLS);
+        self.load(R12, POINTER);
+        self.load(R13, STEPS);
+        self.load(R14, FUEL);
+        self.bytes(&[0x41, 0x8B, 0x6F, MAX]);
+        self.bytes(&[0xFF, 0xE6]);
+    }
+
+    fn epilogue(&mut self) {
+        self.label(Label::Failed);
+        self.bytes(&[0xB8, 0x01, 0x00, 0x00, 0x00]);
+        self.label(Label::Epilogue);
+        self.bytes(&[0x48, 0x83, 0xC4, 0x08]);
+        self.bytes(&[
+            0x41, 0x5F, 0x41, 0x5E, 0x41, 0x5D, 0x41, 0x5C, 0x5D, 0x5B, 0xC3,
+        ]);
+    }
+
+    fn halt(&mut self, counter: usize) {
+        self.label(Label::Instruction(counter));
+        self.synchronise();
+        self.bytes(&[0x31, 0xC0]);
+        self.jump(&[0xE9], Label::Epilogue);
+    }
+
+    fn synchronise(&mut self) {
+        self.store(POINTER, R12);
+        self.store(STEPS, R13);
+    }
+
+    fn instruction(&mut self, counter: usize, operation: Operation, fuel: bool, clock: bool) {
+        self.label(Label::Instruction(counter));
+        let slow = Label::Slow(counter);
+        let units = match operation {
+            Operation::Add(amount) => i32::try_from(amount.unsigned_abs()).ok(),
+            Operation::Move(distance) => i32::try_from(distance.unsigned_abs()).ok(),
+            Operation::JumpAhead(_) | Operation::JumpBack(_) => Some(1),
+            _ => None,
+        };
+        let units = match units {
+            Some(units) => units as u32,
+            None => return self.jump(&[0xE9], slow),
+        };
+        if fuel {
+            self.bytes(&[0x4C, 0x89, 0xF0, 0x4C, 0x29, 0xE8, 0x48, 0x3D]);
+            self.immediate(units);
+            self.jump(&[0x0F, 0x82], slow);
+        }
+        match operation {
+            Operation::Add(amount) if amount > 0 => {
+                self.bytes(&[0x42, 0x8B, 0x04, 0xA3, 0x89, 0xE9, 0x29, 0xC1, 0x81, 0xF9]);
+                self.immediate(units);
+                self.jump(&[0x0F, 0x82], slow);
+                self.bytes(&[0x05]);
+                self.immediate(units);
+                self.bytes(&[0x42, 0x89, 0x04, 0xA3]);
+            }
+            Operation::Add(_) => {
+                self.bytes(&[0x42, 0x8B, 0x04, 0xA3, 0x3D]);
+                self.immediate(units);
+                self.jump(&[0x0F, 0x82], slow);
+                self.bytes(&[0x2D]);
+                self.immediate(units);
+                self.bytes(&[0x42, 0x89, 0x04, 0xA3]);
+            }
+            Operation::Move(distance) if distance > 0 => {
+                self.bytes(&[0x49, 0x8D, 0x84, 0x24]);
+                self.immediate(units);
+                self.bytes(&[0x49, 0x3B, 0x47, LENGTH]);
+                self.jump(&[0x0F, 0x83], slow);
+                self.bytes(&[0x49, 0x89, 0xC4]);
+            }
+            Operation::Move(_) => {
+                self.bytes(&[0x49, 0x81, 0xFC]);
+                self.immediate(units);
+                self.jump(&[0x0F, 0x82], slow);
+                self.bytes(&[0x49, 0x81, 0xEC]);
+                self.immediate(units);
+            }
+            _ => {}
+        }
+        self.bytes(&[0x49, 0x81, 0xC5]);
+        self.immediate(units);
+        match operation {
+            Operation::JumpAhead(closing) => {
+                self.bytes(&[0x42, 0x83, 0x3C, 0xA3, 0x00]);
+                self.jump(&[0x0F, 0x84], Label::Instruction(closing + 1));
+            }
+            Operation::JumpBack(opening) => {
+                self.bytes(&[0x42, 0x83, 0x3C, 0xA3, 0x00]);
+                let target = if clock {
+                    Label::Clock(counter)
+                } else {
+                    Label::Instruction(opening + 1)
+                };
+                self.jump(&[0x0F, 0x85], target);
+            }
+            _ => {}
+        }
+    }
+
+    fn call(&mut self, callback: u8, counter: usize) {
+        self.synchronise();
+        self.bytes(&[0x4C, 0x89, 0xFF, 0xBE]);
+        self.immediate(counter as u32);
+        self.bytes(&[0x41, 0xFF, 0x57, callback]);
+        self.bytes(&[0x48, 0x83, 0xF8, 0xFF]);
+        self.jump(&[0x0F, 0x84], Label::Failed);
+    }
+
+    fn slow(&mut self, counter: usize, successors: &[usize]) {
+        self.label(Label::Slow(counter));
+        self.call(CALLBACK, counter);
+        self.load(RBX, CELLS);
+        self.load(R12, POINTER);
+        self.load(R13, STEPS);
+        let (last, others) = successors.split_last().unwrap();
+        for successor in others {
+            self.bytes(&[0x48, 0x3D]);
+            self.immediate(*successor as u32);
+            self.jump(&[0x0F, 0x84], Label::Instruction(*successor));
+        }
+        self.jump(&[0xE9], Label::Instruction(*last));
+    }
+
+    fn clock(&mut self, counter: usize, target: usize) {
+        self.label(Label::Clock(counter));
+        self.bytes(&[0x49, 0xFF, 0x4F, TICKS]);
+        self.jump(&[0x0F, 0x85], Label::Instruction(target));
+        self.call(CLOCK, target);
+        self.jump(&[0xE9], Label::Instruction(target));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::config::{Backend, MachineConfig};
+    use super::super::error::MachineErrorKind;
+    use super::super::parser::parse;
+    use super::super::run;
+    use std::time::Duration;
+
+    #[test]
+    fn jit_should_stop_after_time_limit() {
+        let config = MachineConfig::default()
+            .backend(Backend::Jit)
+            .time_limit(Duration::from_millis(10));
+
+        let instructions = parse(b"+[]").unwrap();
+
+        assert_eq!(
+            run(&instructions, config).map_err(|error| error.kind),
+            Err(MachineErrorKind::TimedOut)
+        );
+    }
+}
@@ -1,7 +1,19 @@
+use super::bytecode;
+use super::config::{Backend, Eof, MachineConfig, Overflow};
+use super::error::{MachineError, MachineErrorKind, Position};
+use super::ir::{self, Node, Op};
+#[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+use super::jit::{self, Jit, State};
+#[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+use std::ffi::c_void;
 use std::fmt::{self, Debug, Formatter};
 use std::io::{Read, Write};
+use std::iter;
+#[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+use std::ptr;
+use std::time::Instant;
 
-const SIZE: usize = 30_000;
+const CLOCK_INTERVAL: u64 = 1024;
 
 pub struct Machine<'a, I: 'a, O: 'a>
 where
@@ -10,10 +22,15 @@ where
 {
     input: Option<Box<I>>,
     output: Option<Box<O>>,
-    instruction_pointer: usize,
     instructions: &'a [Command],
+    program: Vec<Instruction>,
+    factors: Vec<(isize, i64)>,
+    counter: usize,
     cell_pointer: usize,
-    cells: [u8; SIZE],
+    cells: Vec<u32>,
+    origin: usize,
+    steps: u64,
+    config: MachineConfig,
 }
 
 impl<'a, I, O> Machine<'a, I, O>
@@ -21,191 +38,630 @@ where
     I: Read,
     O: Write,
 {
-    pub fn new(instructions: &'a [Command]) -> Machine<'a, I, O> {
-        Machine {
+    pub fn new(
+        instructions: &'a [Command],
+        config: MachineConfig,
+    ) -> Result<Machine<'a, I, O>, MachineError> {
+        let (program, factors) = program(instructions, config)?;
+        Ok(Machine {
             input: None,
             output: None,
-            instruction_pointer: 0,
-            instructions: instructions,
+            instructions,
+            program,
+            factors,
+            counter: 0,
             cell_pointer: 0,
-            cells: [0; SIZE],
-        }
+            cells: tape(config)?,
+            origin: 0,
+            steps: 0,
+            config,
+        })
     }
 
-    pub fn io(instructions: &'a [Command], input: Box<I>, output: Box<O>) -> Machine<'a, I, O> {
-        Machine {
+    pub fn io(
+        instructions: &'a [Command],
+        input: Box<I>,
+        output: Box<O>,
+        config: MachineConfig,
+    ) -> Result<Machine<'a, I, O>, MachineError> {
+        let (program, factors) = program(instructions, config)?;
+        Ok(Machine {
             input: Some(input),
             output: Some(output),
-            instruction_pointer: 0,
-            instructions: instructions,
+            instructions,
+            program,
+            factors,
+            counter: 0,
             cell_pointer: 0,
-            cells: [0; SIZE],
-        }
+            cells: tape(config)?,
+            origin: 0,
+            steps: 0,
+            config,
+        })
     }
 
     pub fn halted(&self) -> bool {
-        self.instructions.len() <= self.instruction_pointer
-    }
-
-    pub fn execute(mut self) -> Result<Machine<'a, I, O>, MachineError> {
-        let command = self.instructions[self.instruction_pointer];
-        match command {
-            Command::IncrementPointer => {
-                if self.cell_pointer != SIZE - 1 {
-                    self.instruction_pointer += 1;
-                    self.cell_pointer += 1;
-                    Ok(self)
-                } else {
-                    Err(MachineError::PointerIncrementOutOfBound)
-                }
+        self.program.len() <= self.counter
+    }
+
+    pub fn instruction_pointer(&self) -> usize {
+        self.program
+            .get(self.counter)
+            .map_or(self.instructions.len(), |instruction| instruction.source)
+    }
+
+    pub fn cell_pointer(&self) -> usize {
+        self.cell_pointer
+    }
+
+    pub fn position(&self) -> Position {
+        Position {
+            instruction_pointer: self.instruction_pointer(),
+            cell_pointer: self.cell_pointer as isize - self.origin as isize,
+            value: self.cells[self.cell_pointer],
+            steps: self.steps,
+        }
+    }
+
+    fn error(&self, kind: MachineErrorKind, offset: u64) -> MachineError {
+        let mut position = self.position();
+        position.instruction_pointer += offset as usize;
+        position.steps += offset;
+        MachineError::new(kind, position)
+    }
+
+    pub fn cell(&self, index: usize) -> Option<u32> {
+        self.cells.get(index).cloned()
+    }
+
+    pub fn step(&mut self) -> Result<StepOutcome, MachineError> {
+        if !self.halted() {
+            let operation = self.program[self.counter].operation;
+            if let Some(counter) = self.shortcut(operation) {
+                self.counter = counter;
+                return Ok(self.outcome());
             }
-            Command::DecrementPointer => {
-                if self.cell_pointer != 0 {
-                    self.instruction_pointer += 1;
-                    self.cell_pointer -= 1;
-                    Ok(self)
-                } else {
-                    Err(MachineError::PointerDecrementOutOfBound)
-                }
+            let units = operation.units();
+            let allowed = match self.config.fuel {
+                Some(fuel) => units.min(fuel.saturating_sub(self.steps)),
+                None => units,
+            };
+            if allowed == 0 {
+                return Err(self.error(MachineErrorKind::OutOfFuel, 0));
             }
-            Command::Increment => {
-                let current_value = self.cells[self.cell_pointer];
-                if current_value != u8::max_value() {
-                    self.instruction_pointer += 1;
-                    self.cells[self.cell_pointer] += 1;
-                    Ok(self)
-                } else {
-                    Err(MachineError::CellOverflow)
-                }
+            let counter = self.execute(operation, allowed)?;
+            if allowed < units {
+                return Err(self.error(MachineErrorKind::OutOfFuel, allowed));
             }
-            Command::Decrement => {
-                let current_value = self.cells[self.cell_pointer];
-                if current_value != u8::min_value() {
-                    self.instruction_pointer += 1;
-                    self.cells[self.cell_pointer] -= 1;
-                    Ok(self)
-                } else {
-                    Err(MachineError::CellUnderflow)
-                }
+            self.counter = counter;
+            self.steps += units;
+        }
+        Ok(self.outcome())
+    }
+
+    fn outcome(&self) -> StepOutcome {
+        if self.halted() {
+            StepOutcome::Halted
+        } else {
+            StepOutcome::Running
+        }
+    }
+
+    fn shortcut(&mut self, operation: Operation) -> Option<usize> {
+        let fallback = self.counter + 1;
+        let closing = match operation {
+            Operation::MulAdd { .. } | Operation::Scan(_) => match self.program[fallback].operation
+            {
+                Operation::JumpAhead(closing) => closing,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let period = (self.program[closing].source - self.program[fallback].source) as u64;
+        let units = match operation {
+            Operation::MulAdd { step, start, end } => self.multiply(step, start, end, period),
+            Operation::Scan(stride) => self.scan(stride, period),
+            _ => None,
+        };
+        match units {
+            Some(units) => {
+                self.steps += units;
+                Some(closing + 1)
             }
-            Command::JumpAhead => {
-                let current_value = self.cells[self.cell_pointer];
-                if current_value == 0 {
-                    if let Some(index) = self.jump_back_index(self.instruction_pointer) {
-                        self.instruction_pointer = index + 1;
-                        Ok(self)
-                    } else {
-                        Err(MachineError::UnmatchedJumpAhead)
-                    }
-                } else {
-                    self.instruction_pointer += 1;
-                    Ok(self)
-                }
+            None => Some(fallback),
+        }
+    }
+
+    fn budget(&self) -> u64 {
+        self.config
+            .fuel
+            .map_or(u64::MAX, |fuel| fuel.saturating_sub(self.steps))
+    }
+
+    fn multiply(&mut self, step: i64, start: usize, end: usize, period: u64) -> Option<u64> {
+        let current_value = u64::from(self.cells[self.cell_pointer]);
+        let modulus = u64::from(self.config.width.max()) + 1;
+        let iterations = match current_value {
+            0 => 0,
+            _ if step < 0 => current_value,
+            _ => modulus - current_value,
+        };
+        let units = 1 + iterations * period;
+        if units > self.budget() {
+            return None;
+        }
+        let mut targets = Vec::with_capacity(end - start);
+        for &(offset, factor) in &self.factors[start..end] {
+            let index = self.cell_pointer as isize + offset;
+            if index < 0 || index as usize >= self.cells.len() {
+                return None;
             }
-            Command::JumpBack => {
-                let current_value = self.cells[self.cell_pointer];
-                if current_value != 0 {
-                    if let Some(index) = self.jump_ahead_index(self.instruction_pointer) {
-                        self.instruction_pointer = index + 1;
-                        Ok(self)
-                    } else {
-                        Err(MachineError::UnmatchedJumpBack)
-                    }
-                } else {
-                    self.instruction_pointer += 1;
-                    Ok(self)
-                }
+            let value = self.added(
+                self.cells[index as usize],
+                i128::from(factor) * i128::from(iterations),
+            )?;
+            targets.push((index as usize, value));
+        }
+        for (index, value) in targets {
+            self.cells[index] = value;
+        }
+        self.cells[self.cell_pointer] = 0;
+        Some(units)
+    }
+
+    fn added(&self, value: u32, amount: i128) -> Option<u32> {
+        let max = i128::from(self.config.width.max());
+        let next_value = i128::from(value) + amount;
+        match self.config.overflow {
+            _ if 0 <= next_value && next_value <= max => Some(next_value as u32),
+            Overflow::Wrap => Some(next_value.rem_euclid(max + 1) as u32),
+            Overflow::Saturate => Some(next_value.clamp(0, max) as u32),
+            Overflow::Error => None,
+        }
+    }
+
+    fn scan(&mut self, stride: isize, period: u64) -> Option<u64> {
+        let mut index = self.cell_pointer;
+        let mut moves = 0;
+        while self.cells[index] != 0 {
+            let next = index as isize + stride;
+            if next < 0 || next as usize >= self.cells.len() {
+                return None;
             }
-            Command::Read => {
-                if self.input.is_none() {
-                    return Err(MachineError::NoInput);
-                }
-                {
-                    let mut input = self.input.as_mut().unwrap();
-                    let mut buffer: [u8; 1] = [0; 1];
-                    if let Ok(size) = (*input).read(&mut buffer) {
-                        if size == 1 {
-                            self.instruction_pointer += 1;
-                            self.cells[self.cell_pointer] = buffer[0];
-                        } else {
-                            return Err(MachineError::NoByteRead);
-                        }
-                    } else {
-                        return Err(MachineError::InputError);
-                    }
+            index = next as usize;
+            moves += 1;
+        }
+        let units = 1 + moves * period;
+        if units > self.budget() {
+            return None;
+        }
+        self.cell_pointer = index;
+        Some(units)
+    }
+
+    fn execute(&mut self, operation: Operation, count: u64) -> Result<usize, MachineError> {
+        match operation {
+            Operation::Add(amount) if amount > 0 => self.increment(count)?,
+            Operation::Add(_) => self.decrement(count)?,
+            Operation::Move(distance) if distance > 0 => self.move_right(count as usize)?,
+            Operation::Move(_) => self.move_left(count as usize)?,
+            Operation::JumpAhead(closing) => {
+                if self.cells[self.cell_pointer] == 0 {
+                    return Ok(closing + 1);
                 }
-                Ok(self)
             }
-            Command::Write => {
-                if self.output.is_none() {
-                    return Err(MachineError::NoOutput);
-                }
-                {
-                    let buffer: [u8; 1] = [self.cells[self.cell_pointer]; 1];
-                    let mut output = self.output.as_mut().unwrap();
-                    if let Ok(size) = (*output).write(&buffer) {
-                        if size == 1 {
-                            self.instruction_pointer += 1;
-                        } else {
-                            return Err(MachineError::NoByteWritten);
-                        }
-                    } else {
-                        return Err(MachineError::OutputError);
-                    }
+            Operation::JumpBack(opening) => {
+                if self.cells[self.cell_pointer] != 0 {
+                    return Ok(opening + 1);
                 }
-                Ok(self)
             }
+            Operation::Read => self.read()?,
+            Operation::Write => self.write()?,
+            Operation::MulAdd { .. } | Operation::Scan(_) => { /* shortcuts are handled by step */ }
         }
+        Ok(self.counter + 1)
     }
 
-    pub fn run(mut self) -> Result<(), MachineError> {
-        while !self.halted() {
-            match self.execute() {
-                Ok(next_machine) => {
-                    self = next_machine;
-                }
+    fn increment(&mut self, count: u64) -> Result<(), MachineError> {
+        let current_value = u64::from(self.cells[self.cell_pointer]);
+        let max = u64::from(self.config.width.max());
+        let next_value = match self.config.overflow {
+            _ if current_value + count <= max => current_value + count,
+            Overflow::Wrap => (current_value + count) % (max + 1),
+            Overflow::Saturate => max,
+            Overflow::Error => {
+                self.cells[self.cell_pointer] = max as u32;
+                return Err(self.error(MachineErrorKind::CellOverflow, max - current_value));
+            }
+        };
+        self.cells[self.cell_pointer] = next_value as u32;
+        Ok(())
+    }
 
-                Err(error) => {
-                    return Err(error);
-                }
+    fn decrement(&mut self, count: u64) -> Result<(), MachineError> {
+        let current_value = u64::from(self.cells[self.cell_pointer]);
+        let modulus = u64::from(self.config.width.max()) + 1;
+        let next_value = match self.config.overflow {
+            _ if count <= current_value => current_value - count,
+            Overflow::Wrap => (current_value + modulus - count % modulus) % modulus,
+            Overflow::Saturate => 0,
+            Overflow::Error => {
+                self.cells[self.cell_pointer] = 0;
+                return Err(self.error(MachineErrorKind::CellUnderflow, current_value));
+            }
+        };
+        self.cells[self.cell_pointer] = next_value as u32;
+        Ok(())
+    }
+
+    fn move_right(&mut self, distance: usize) -> Result<(), MachineError> {
+        let mut length = self.cells.len();
+        if self.cell_pointer + distance >= length {
+            if !self.config.unbounded {
+                let offset = length - 1 - self.cell_pointer;
+                self.cell_pointer = length - 1;
+                return Err(self.error(MachineErrorKind::PointerIncrementOutOfBound, offset as u64));
             }
+            while self.cell_pointer + distance >= length {
+                length *= 2;
+            }
+            self.cells.resize(length, 0);
         }
+        self.cell_pointer += distance;
         Ok(())
     }
 
-    fn jump_back_index(&self, start_index: usize) -> Option<usize> {
-        let mut openings = 1;
-        let mut index = start_index + 1;
-        while index < self.instructions.len() && openings != 0 {
-            match self.instructions[index] {
-                Command::JumpAhead => openings += 1,
-                Command::JumpBack => openings -= 1,
-                _ => { /* do nothing */ }
+    fn move_left(&mut self, distance: usize) -> Result<(), MachineError> {
+        if self.cell_pointer < distance {
+            if !self.config.unbounded {
+                let offset = self.cell_pointer;
+                self.cell_pointer = 0;
+                return Err(self.error(MachineErrorKind::PointerDecrementOutOfBound, offset as u64));
+            }
+            let mut length = self.cells.len();
+            while self.cell_pointer + length - self.cells.len() < distance {
+                length *= 2;
             }
-            index += 1
+            let growth = length - self.cells.len();
+            self.cells.splice(0..0, iter::repeat(0).take(growth));
+            self.origin += growth;
+            self.cell_pointer += growth;
+        }
+        self.cell_pointer -= distance;
+        Ok(())
+    }
+
+    fn read(&mut self) -> Result<(), MachineError> {
+        if self.input.is_none() {
+            return Err(self.error(MachineErrorKind::NoInput, 0));
+        }
+        let input = self.input.as_mut().unwrap();
+        let mut buffer: [u8; 1] = [0; 1];
+        if let Ok(size) = (*input).read(&mut buffer) {
+            if size == 1 {
+                self.cells[self.cell_pointer] = u32::from(buffer[0]);
+            } else {
+                match self.config.eof {
+                    Eof::Error => return Err(self.error(MachineErrorKind::NoByteRead, 0)),
+                    Eof::Zero => self.cells[self.cell_pointer] = 0,
+                    Eof::MinusOne => self.cells[self.cell_pointer] = self.config.width.max(),
+                    Eof::Unchanged => { /* leave the cell as it is */ }
+                }
+            }
+            Ok(())
+        } else {
+            Err(self.error(MachineErrorKind::InputError, 0))
+        }
+    }
+
+    fn write(&mut self) -> Result<(), MachineError> {
+        if self.output.is_none() {
+            return Err(self.error(MachineErrorKind::NoOutput, 0));
         }
-        if index <= self.instructions.len() && openings == 0 {
-            Some(index - 1)
+        let buffer: [u8; 1] = [self.cells[self.cell_pointer] as u8; 1];
+        let output = self.output.as_mut().unwrap();
+        if let Ok(size) = (*output).write(&buffer) {
+            if size == 1 {
+                Ok(())
+            } else {
+                Err(self.error(MachineErrorKind::NoByteWritten, 0))
+            }
         } else {
-            None
+            Err(self.error(MachineErrorKind::OutputError, 0))
+        }
+    }
+
+    pub fn run(&mut self) -> Result<(), MachineError> {
+        match self.config.backend {
+            Backend::Bytecode => self.run_bytecode(),
+            #[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+            Backend::Jit => self.run_jit(),
+            _ => self.interpret(),
+        }
+    }
+
+    fn interpret(&mut self) -> Result<(), MachineError> {
+        let deadline = self.config.time_limit.map(|limit| Instant::now() + limit);
+        let mut ticks: u64 = 0;
+        loop {
+            if let Some(deadline) = deadline {
+                if ticks % CLOCK_INTERVAL == 0 && Instant::now() >= deadline {
+                    return Err(self.error(MachineErrorKind::TimedOut, 0));
+                }
+            }
+            if self.step()? == StepOutcome::Halted {
+                return Ok(());
+            }
+            ticks += 1;
         }
     }
 
-    fn jump_ahead_index(&self, start_index: usize) -> Option<usize> {
-        let mut closings = 1;
-        let mut index: isize = start_index as isize - 1;
-        while index >= 0 && closings != 0 {
-            match self.instructions[index as usize] {
-                Command::JumpAhead => closings -= 1,
-                Command::JumpBack => closings += 1,
-                _ => { /* do nothing */ }
+    fn run_bytecode(&mut self) -> Result<(), MachineError> {
+        let code = bytecode::compile(&self.program);
+        let deadline = self.config.time_limit.map(|limit| Instant::now() + limit);
+        let fuel = self.config.fuel.unwrap_or(u64::MAX);
+        let max = self.config.width.max();
+        let mut pc = bytecode::WIDTH * self.counter;
+        let mut ticks: u64 = 0;
+        while pc < code.len() {
+            if let Some(deadline) = deadline {
+                if ticks % CLOCK_INTERVAL == 0 && Instant::now() >= deadline {
+                    self.counter = pc / bytecode::WIDTH;
+                    return Err(self.error(MachineErrorKind::TimedOut, 0));
+                }
+            }
+            ticks += 1;
+            let operand = code[pc + 1];
+            let units = match code[pc] {
+                bytecode::OPEN | bytecode::CLOSE => 1,
+                _ => u64::from(operand),
+            };
+            if units <= fuel - self.steps {
+                let value = self.cells[self.cell_pointer];
+                let next = match code[pc] {
+                    bytecode::INCREMENT if operand <= max - value => {
+                        self.cells[self.cell_pointer] = value + operand;
+                        Some(pc + bytecode::WIDTH)
+                    }
+                    bytecode::DECREMENT if operand <= value => {
+                        self.cells[self.cell_pointer] = value - operand;
+                        Some(pc + bytecode::WIDTH)
+                    }
+                    bytecode::RIGHT
+                        if (operand as usize) < self.cells.len() - self.cell_pointer =>
+                    {
+                        self.cell_pointer += operand as usize;
+                        Some(pc + bytecode::WIDTH)
+                    }
+                    bytecode::LEFT if operand as usize <= self.cell_pointer => {
+                        self.cell_pointer -= operand as usize;
+                        Some(pc + bytecode::WIDTH)
+                    }
+                    bytecode::OPEN if value == 0 => Some(operand as usize),
+                    bytecode::CLOSE if value != 0 => Some(operand as usize),
+                    bytecode::OPEN | bytecode::CLOSE => Some(pc + bytecode::WIDTH),
+                    _ => None,
+                };
+                if let Some(next) = next {
+                    self.steps += units;
+                    pc = next;
+                    continue;
+                }
             }
-            index -= 1
+            self.counter = pc / bytecode::WIDTH;
+            self.step()?;
+            pc = bytecode::WIDTH * self.counter;
         }
-        if index >= 0 && closings == 0 {
-            Some(index as usize + 1)
+        self.counter = pc / bytecode::WIDTH;
+        Ok(())
+    }
+
+    #[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+    fn run_jit(&mut self) -> Result<(), MachineError> {
+        let jit = match Jit::compile(
+            &self.program,
+            self.config.fuel.is_some(),
+            self.config.time_limit.is_some(),
+        ) {
+            Some(jit) => jit,
+            None => return self.interpret(),
+        };
+        let counter = self.counter;
+        let mut state = State {
+            cells: self.cells.as_mut_ptr(),
+            length: self.cells.len() as u64,
+            pointer: self.cell_pointer as u64,
+            steps: self.steps,
+            fuel: self.config.fuel.unwrap_or(u64::MAX),
+            max: u64::from(self.config.width.max()),
+            ticks: CLOCK_INTERVAL,
+            context: ptr::null_mut(),
+            callback: callback::<I, O>,
+            clock: clock::<I, O>,
+        };
+        let mut context = Context {
+            deadline: self.config.time_limit.map(|limit| Instant::now() + limit),
+            problem: None,
+            machine: self,
+        };
+        state.context = &mut context as *mut Context<I, O> as *mut c_void;
+        if jit.run(&mut state, counter) {
+            let machine = context.machine;
+            machine.cell_pointer = state.pointer as usize;
+            machine.steps = state.steps;
+            machine.counter = machine.program.len();
+            Ok(())
         } else {
-            None
+            Err(context.problem.unwrap())
+        }
+    }
+}
+
+#[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+struct Context<'a, 'm, I: 'a, O: 'a>
+where
+    I: Read,
+    O: Write,
+{
+    deadline: Option<Instant>,
+    problem: Option<MachineError>,
+    machine: &'m mut Machine<'a, I, O>,
+}
+
+#[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+extern "sysv64" fn callback<I: Read, O: Write>(state: *mut State, counter: u64) -> u64 {
+    let state = unsafe { &mut *state };
+    let context = unsafe { &mut *(state.context as *mut Context<I, O>) };
+    let machine = &mut *context.machine;
+    machine.cell_pointer = state.pointer as usize;
+    machine.steps = state.steps;
+    machine.counter = counter as usize;
+    match machine.step() {
+        Ok(_) => {
+            state.cells = machine.cells.as_mut_ptr();
+            state.length = machine.cells.len() as u64;
+            state.pointer = machine.cell_pointer as u64;
+            state.steps = machine.steps;
+            machine.counter as u64
+        }
+        Err(problem) => {
+            context.problem = Some(problem);
+            jit::FAILED
+        }
+    }
+}
+
+#[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+extern "sysv64" fn clock<I: Read, O: Write>(state: *mut State, counter: u64) -> u64 {
+    let state = unsafe { &mut *state };
+    let context = unsafe { &mut *(state.context as *mut Context<I, O>) };
+    state.ticks = CLOCK_INTERVAL;
+    match context.deadline {
+        Some(deadline) if Instant::now() >= deadline => {
+            let machine = &mut *context.machine;
+            machine.cell_pointer = state.pointer as usize;
+            machine.steps = state.steps;
+            machine.counter = counter as usize;
+            context.problem = Some(machine.error(MachineErrorKind::TimedOut, 0));
+            jit::FAILED
+        }
+        _ => 0,
+    }
+}
+
+fn tape(config: MachineConfig) -> Result<Vec<u32>, MachineError> {
+    if config.tape > 0 {
+        Ok(vec![0; config.tape])
+    } else {
+        Err(MachineError::new(
+            MachineErrorKind::EmptyTape,
+            Position::default(),
+        ))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Instruction {
+    pub operation: Operation,
+    pub source: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Operation {
+    Add(i64),
+    Move(isize),
+    JumpAhead(usize),
+    JumpBack(usize),
+    Read,
+    Write,
+    MulAdd { step: i64, start: usize, end: usize },
+    Scan(isize),
+}
+
+impl Operation {
+    fn units(&self) -> u64 {
+        match *self {
+            Operation::Add(amount) => amount.unsigned_abs(),
+            Operation::Move(distance) => distance.unsigned_abs() as u64,
+            _ => 1,
+        }
+    }
+}
+
+type Program = (Vec<Instruction>, Vec<(isize, i64)>);
+
+fn program(instructions: &[Command], config: MachineConfig) -> Result<Program, MachineError> {
+    let nodes = ir::optimise(ir::lower(instructions)?, config.overflow);
+    let mut program = (vec![], vec![]);
+    flatten(&nodes, instructions, &mut program);
+    Ok(program)
+}
+
+fn flatten(nodes: &[Node], instructions: &[Command], program: &mut Program) {
+    for node in nodes {
+        let operation = match node.op {
+            Op::Add(amount) => Operation::Add(amount),
+            Op::Move(distance) => Operation::Move(distance),
+            Op::Read => Operation::Read,
+            Op::Write => Operation::Write,
+            Op::Loop(ref body) => {
+                let opening = program.0.len();
+                program.0.push(Instruction {
+                    operation: Operation::JumpAhead(0),
+                    source: node.source.start,
+                });
+                flatten(body, instructions, program);
+                let closing = program.0.len();
+                program.0[opening].operation = Operation::JumpAhead(closing);
+                program.0.push(Instruction {
+                    operation: Operation::JumpBack(opening),
+                    source: node.source.end - 1,
+                });
+                continue;
+            }
+            Op::Clear | Op::MulAdd(_) | Op::Scan(_) => {
+                let fallback = fallback(instructions, node);
+                let operation = match (&node.op, &fallback.op) {
+                    (Op::Scan(stride), _) => Operation::Scan(*stride),
+                    (_, Op::Loop(body)) => {
+                        let (step, factors) = ir::balance(body).unwrap();
+                        let start = program.1.len();
+                        program.1.extend(factors);
+                        Operation::MulAdd {
+                            step,
+                            start,
+                            end: program.1.len(),
+                        }
+                    }
+                    _ => unreachable!(),
+                };
+                program.0.push(Instruction {
+                    operation,
+                    source: node.source.start,
+                });
+                flatten(&[fallback], instructions, program);
+                continue;
+            }
+        };
+        program.0.push(Instruction {
+            operation,
+            source: node.source.start,
+        });
+    }
+}
+
+fn fallback(instructions: &[Command], node: &Node) -> Node {
+    let mut nodes = ir::lower(&instructions[node.source.clone()]).unwrap();
+    shift(&mut nodes, node.source.start);
+    nodes.remove(0)
+}
+
+fn shift(nodes: &mut [Node], offset: usize) {
+    for node in nodes {
+        node.source.start += offset;
+        node.source.end += offset;
+        if let Op::Loop(ref mut body) = node.op {
+            shift(body, offset);
         }
     }
 }
@@ -216,30 +672,22 @@ where
     O: Write,
 {
     fn eq(&self, rhs: &Self) -> bool {
-        if self.instruction_pointer != rhs.instruction_pointer {
+        if self.instruction_pointer() != rhs.instruction_pointer() {
             return false;
         }
         if self.instructions != rhs.instructions {
             return false;
         }
-        if self.cell_pointer != rhs.cell_pointer {
+        if self.cell_pointer != rhs.cell_pointer || self.origin != rhs.origin {
             return false;
         }
-        if !same_cells(&self.cells, &rhs.cells) {
+        if self.cells != rhs.cells {
             return false;
         }
         true
     }
 }
 
-fn same_cells(lhs: &[u8; SIZE], rhs: &[u8; SIZE]) -> bool {
-    let mut index = 0;
-    while index < SIZE && lhs[index] == rhs[index] {
-        index += 1;
-    }
-    index == SIZE
-}
-
 impl<'a, I, O> Eq for Machine<'a, I, O>
 where
     I: Read,
@@ -253,34 +701,24 @@ where
     O: Write,
 {
     fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
-        write!(f, "<{}:[", self.instruction_pointer)?;
+        write!(f, "<{}:[", self.instruction_pointer())?;
         for instruction in self.instructions {
             write!(f, " {:?}", instruction)?;
         }
-        write!(f, " ]|{};{{", self.cell_pointer)?;
-        for index in 0..SIZE {
-            if self.cells[index] != 0 {
-                write!(f, "({},{})", index, self.cells[index])?;
+        write!(f, " ]|{};{{", self.position().cell_pointer)?;
+        for (index, cell) in self.cells.iter().enumerate() {
+            if *cell != 0 {
+                write!(f, "({},{})", index as isize - self.origin as isize, cell)?;
             }
         }
         write!(f, "}}>")
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum MachineError {
-    PointerIncrementOutOfBound,
-    PointerDecrementOutOfBound,
-    CellOverflow,
-    CellUnderflow,
-    UnmatchedJumpAhead,
-    UnmatchedJumpBack,
-    NoInput,
-    InputError,
-    NoByteRead,
-    NoOutput,
-    OutputError,
-    NoByteWritten,
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StepOutcome {
+    Running,
+    Halted,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -296,6 +734,17 @@ pub enum Command {
 }
 
 impl Command {
+    pub const ALL: [Command; 8] = [
+        Command::IncrementPointer,
+        Command::DecrementPointer,
+        Command::Increment,
+        Command::Decrement,
+        Command::JumpAhead,
+        Command::JumpBack,
+        Command::Write,
+        Command::Read,
+    ];
+
     pub fn to_brnfck(&self) -> char {
         match self {
             Command::IncrementPointer => '>',
@@ -351,13 +800,16 @@ impl Command {
 
 #[cfg(test)]
 mod tests {
+    use super::super::config::{CellWidth, TAPE};
+    use super::super::parser::parse;
     use super::*;
+    use std::time::Duration;
 
     struct BuildMachine<'a> {
         instruction_pointer: usize,
         instructions: &'a [Command],
         cell_pointer: usize,
-        cells: [u8; SIZE],
+        cells: Vec<u32>,
     }
 
     impl<'a> BuildMachine<'a> {
@@ -366,7 +818,7 @@ mod tests {
                 instruction_pointer: 0,
                 instructions,
                 cell_pointer: 0,
-                cells: [0; SIZE],
+                cells: vec![0; TAPE],
             }
         }
 
@@ -388,7 +840,7 @@ mod tests {
             }
         }
 
-        fn cell(mut self, index: usize, value: u8) -> Self {
+        fn cell(mut self, index: usize, value: u32) -> Self {
             self.cells[index] = value;
             BuildMachine {
                 instruction_pointer: self.instruction_pointer,
@@ -399,13 +851,22 @@ mod tests {
         }
 
         fn build(self) -> Machine<'a, &'a [u8], Vec<u8>> {
+            let (program, factors) = program(self.instructions, MachineConfig::default()).unwrap();
             Machine {
                 input: None,
                 output: None,
-                instruction_pointer: self.instruction_pointer,
                 instructions: self.instructions,
+                counter: program
+                    .iter()
+                    .position(|instruction| instruction.source >= self.instruction_pointer)
+                    .unwrap_or(program.len()),
+                program,
+                factors,
                 cell_pointer: self.cell_pointer,
                 cells: self.cells,
+                origin: 0,
+                steps: 0,
+                config: MachineConfig::default(),
             }
         }
     }
@@ -417,10 +878,12 @@ mod tests {
             .instruction_pointer_at(1)
             .cell_pointer_at(1)
             .build();
-        let machine: Machine<&[u8], Vec<u8>> = Machine::new(&instructions);
+        let mut machine: Machine<&[u8], Vec<u8>> =
+            Machine::new(&instructions, MachineConfig::default()).unwrap();
 
-        if let Ok(result_machine) = machine.execute() {
-            assert_eq!(result_machine, expected_machine);
+        if let Ok(outcome) = machine.step() {
+            assert_eq!(outcome, StepOutcome::Halted);
+            assert_eq!(machine, expected_machine);
         } else {
             assert!(false);
         }
@@ -433,10 +896,12 @@ mod tests {
             .instruction_pointer_at(1)
             .cell(0, 1)
             .build();
-        let machine: Machine<&[u8], Vec<u8>> = Machine::new(&instructions);
+        let mut machine: Machine<&[u8], Vec<u8>> =
+            Machine::new(&instructions, MachineConfig::default()).unwrap();
 
-        if let Ok(result_machine) = machine.execute() {
-            assert_eq!(result_machine, expected_machine);
+        if let Ok(outcome) = machine.step() {
+            assert_eq!(outcome, StepOutcome::Halted);
+            assert_eq!(machine, expected_machine);
         } else {
             assert!(false);
         }
@@ -444,20 +909,21 @@ mod tests {
 
     #[test]
     fn execute_instruction_will_result_in_an_error() {
-        for (instruction, expected_error) in vec![
+        for (instruction, expected_error) in [
             (
                 Command::DecrementPointer,
-                MachineError::PointerDecrementOutOfBound,
+                MachineErrorKind::PointerDecrementOutOfBound,
             ),
-            (Command::Decrement, MachineError::CellUnderflow),
-            (Command::Read, MachineError::NoInput),
-            (Command::Write, MachineError::NoOutput),
+            (Command::Decrement, MachineErrorKind::CellUnderflow),
+            (Command::Read, MachineErrorKind::NoInput),
+            (Command::Write, MachineErrorKind::NoOutput),
         ] {
             let instructions = [instruction];
-            let machine: Machine<&[u8], Vec<u8>> = Machine::new(&instructions);
+            let mut machine: Machine<&[u8], Vec<u8>> =
+                Machine::new(&instructions, MachineConfig::default()).unwrap();
 
-            if let Err(result_error) = machine.execute() {
-                assert_eq!(result_error, expected_error);
+            if let Err(result_error) = machine.step() {
+                assert_eq!(result_error.kind, expected_error);
             } else {
                 assert!(false);
             }
@@ -467,12 +933,15 @@ mod tests {
     #[test]
     fn increment_pointer_should_error_when_on_boundary() {
         let instructions = [Command::IncrementPointer];
-        let machine = BuildMachine::with(&instructions)
-            .cell_pointer_at(SIZE - 1)
+        let mut machine = BuildMachine::with(&instructions)
+            .cell_pointer_at(TAPE - 1)
             .build();
 
-        if let Err(result_error) = machine.execute() {
-            assert_eq!(result_error, MachineError::PointerIncrementOutOfBound);
+        if let Err(result_error) = machine.step() {
+            assert_eq!(
+                result_error.kind,
+                MachineErrorKind::PointerIncrementOutOfBound
+            );
         } else {
             assert!(false);
         }
@@ -481,12 +950,10 @@ mod tests {
     #[test]
     fn increment_should_error_when_on_around() {
         let instructions = [Command::Increment];
-        let machine = BuildMachine::with(&instructions)
-            .cell(0, u8::max_value())
-            .build();
+        let mut machine = BuildMachine::with(&instructions).cell(0, 255).build();
 
-        if let Err(result_error) = machine.execute() {
-            assert_eq!(result_error, MachineError::CellOverflow);
+        if let Err(result_error) = machine.step() {
+            assert_eq!(result_error.kind, MachineErrorKind::CellOverflow);
         } else {
             assert!(false);
         }
@@ -495,10 +962,11 @@ mod tests {
     #[test]
     fn jump_ahead_should_error_when_missing_jump_back() {
         let instructions = [Command::JumpAhead];
-        let machine: Machine<&[u8], Vec<u8>> = Machine::new(&instructions);
+        let machine: Result<Machine<&[u8], Vec<u8>>, MachineError> =
+            Machine::new(&instructions, MachineConfig::default());
 
-        if let Err(result_error) = machine.execute() {
-            assert_eq!(result_error, MachineError::UnmatchedJumpAhead);
+        if let Err(result_error) = machine {
+            assert_eq!(result_error.kind, MachineErrorKind::UnmatchedJumpAhead);
         } else {
             assert!(false);
         }
@@ -506,11 +974,12 @@ mod tests {
 
     #[test]
     fn jump_back_should_error_when_missing_jump_ahead() {
-        let instructions = [Command::JumpBack];
-        let machine = BuildMachine::with(&instructions).cell(0, 1).build();
+        let instructions = [Command::JumpAhead, Command::JumpBack, Command::JumpBack];
+        let machine: Result<Machine<&[u8], Vec<u8>>, MachineError> =
+            Machine::new(&instructions, MachineConfig::default());
 
-        if let Err(result_error) = machine.execute() {
-            assert_eq!(result_error, MachineError::UnmatchedJumpBack);
+        if let Err(result_error) = machine {
+            assert_eq!(result_error.kind, MachineErrorKind::UnmatchedJumpBack);
         } else {
             assert!(false);
         }
@@ -519,85 +988,35 @@ mod tests {
     #[test]
     fn jumping_should_work_correctly() {
         let instructions = [
+            Command::Increment,
+            Command::Increment,
             Command::Increment,
             Command::Increment,
             Command::JumpAhead,
             Command::Decrement,
+            Command::Decrement,
             Command::JumpBack,
         ];
-        let machine: Machine<&[u8], Vec<u8>> = Machine::new(&instructions);
+        let mut machine: Machine<&[u8], Vec<u8>> =
+            Machine::new(&instructions, MachineConfig::default()).unwrap();
 
-        if let Ok(result_machine) = machine
-            .execute()
-            .and_then(|machine| {
-                assert_eq!(
-                    machine,
-                    BuildMachine::with(&instructions)
-                        .instruction_pointer_at(1)
-                        .cell(0, 1)
-                        .build()
-                );
-                machine.execute()
-            })
-            .and_then(|machine| {
-                assert_eq!(
-                    machine,
-                    BuildMachine::with(&instructions)
-                        .instruction_pointer_at(2)
-                        .cell(0, 2)
-                        .build()
-                );
-                machine.execute()
-            })
-            .and_then(|machine| {
-                assert_eq!(
-                    machine,
-                    BuildMachine::with(&instructions)
-                        .instruction_pointer_at(3)
-                        .cell(0, 2)
-                        .build()
-                );
-                machine.execute()
-            })
-            .and_then(|machine| {
-                assert_eq!(
-                    machine,
-                    BuildMachine::with(&instructions)
-                        .instruction_pointer_at(4)
-                        .cell(0, 1)
-                        .build()
-                );
-                machine.execute()
-            })
-            .and_then(|machine| {
-                assert_eq!(
-                    machine,
-                    BuildMachine::with(&instructions)
-                        .instruction_pointer_at(3)
-                        .cell(0, 1)
-                        .build()
-                );
-                machine.execute()
-            })
-            .and_then(|machine| {
-                assert_eq!(
-                    machine,
-                    BuildMachine::with(&instructions)
-                        .instruction_pointer_at(4)
-                        .build()
-                );
-                machine.execute()
-            })
-        {
+        for (instruction_pointer, value) in [(4, 4), (5, 4), (7, 2), (5, 2), (7, 0)] {
+            assert_eq!(machine.step(), Ok(StepOutcome::Running));
             assert_eq!(
-                result_machine,
+                machine,
                 BuildMachine::with(&instructions)
-                    .instruction_pointer_at(5)
+                    .instruction_pointer_at(instruction_pointer)
+                    .cell(0, value)
                     .build()
             );
-        } else {
-            assert!(false);
         }
+        assert_eq!(machine.step(), Ok(StepOutcome::Halted));
+        assert_eq!(
+            machine,
+            BuildMachine::with(&instructions)
+                .instruction_pointer_at(8)
+                .build()
+        );
     }
 
     #[test]
@@ -629,4 +1048,411 @@ mod tests {
 
         assert!(machine.halted());
     }
+
+    #[test]
+    fn jumps_should_be_paired_with_their_match() {
+        let instructions = [
+            Command::JumpAhead,
+            Command::JumpAhead,
+            Command::Increment,
+            Command::JumpBack,
+            Command::JumpAhead,
+            Command::JumpBack,
+            Command::JumpBack,
+        ];
+        let jumps: Vec<(Operation, usize)> = program(&instructions, MachineConfig::default())
+            .unwrap()
+            .0
+            .into_iter()
+            .map(|instruction| (instruction.operation, instruction.source))
+            .collect();
+
+        assert_eq!(
+            jumps,
+            vec![
+                (Operation::JumpAhead(6), 0),
+                (Operation::JumpAhead(3), 1),
+                (Operation::Add(1), 2),
+                (Operation::JumpBack(1), 3),
+                (Operation::JumpAhead(5), 4),
+                (Operation::JumpBack(4), 5),
+                (Operation::JumpBack(0), 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn folded_increment_should_overflow_at_the_same_command() {
+        let instructions = [Command::Increment, Command::Increment, Command::Increment];
+        let mut machine = BuildMachine::with(&instructions).cell(0, 254).build();
+
+        assert_eq!(
+            machine.step(),
+            Err(MachineError::new(
+                MachineErrorKind::CellOverflow,
+                Position {
+                    instruction_pointer: 1,
+                    cell_pointer: 0,
+                    value: 255,
+                    steps: 1,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn folded_move_should_leave_tape_at_the_same_command() {
+        let instructions = [Command::DecrementPointer; 3];
+        let mut machine = BuildMachine::with(&instructions).cell_pointer_at(1).build();
+
+        if let Err(error) = machine.step() {
+            assert_eq!(error.kind, MachineErrorKind::PointerDecrementOutOfBound);
+            assert_eq!(error.position.instruction_pointer, 1);
+            assert_eq!(error.position.cell_pointer, 0);
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn multiply_loop_should_run_in_a_single_step() {
+        let instructions = parse(b"[->+>++<<]").unwrap();
+        let mut machine = BuildMachine::with(&instructions).cell(0, 3).build();
+
+        assert_eq!(machine.step(), Ok(StepOutcome::Halted));
+        assert_eq!(
+            (0..3)
+                .map(|index| machine.cells[index])
+                .collect::<Vec<u32>>(),
+            vec![0, 3, 6]
+        );
+        assert_eq!(machine.steps, 28);
+    }
+
+    #[test]
+    fn multiply_loop_should_fall_back_when_a_target_would_overflow() {
+        let instructions = parse(b"[->+<]").unwrap();
+        let mut machine = BuildMachine::with(&instructions)
+            .cell(0, 3)
+            .cell(1, 254)
+            .build();
+
+        assert_eq!(
+            machine.run(),
+            Err(MachineError::new(
+                MachineErrorKind::CellOverflow,
+                Position {
+                    instruction_pointer: 3,
+                    cell_pointer: 1,
+                    value: 255,
+                    steps: 8,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn scan_loop_should_stop_at_first_empty_cell() {
+        let instructions = parse(b"[>>]").unwrap();
+        let mut machine = BuildMachine::with(&instructions)
+            .cell(0, 1)
+            .cell(1, 0)
+            .cell(2, 1)
+            .build();
+
+        assert_eq!(machine.step(), Ok(StepOutcome::Halted));
+        assert_eq!(machine.cell_pointer(), 4);
+        assert_eq!(machine.steps, 7);
+    }
+
+    #[test]
+    fn clear_loop_should_fall_back_when_out_of_fuel() {
+        let instructions = parse(b"[-]").unwrap();
+        let config = MachineConfig::default().fuel(5);
+        let mut machine: Machine<&[u8], Vec<u8>> = Machine::new(&instructions, config).unwrap();
+        machine.cells[0] = 10;
+
+        assert_eq!(
+            machine.run(),
+            Err(MachineError::new(
+                MachineErrorKind::OutOfFuel,
+                Position {
+                    instruction_pointer: 1,
+                    cell_pointer: 0,
+                    value: 8,
+                    steps: 5,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn step_should_keep_reporting_halted_machine() {
+        let instructions = [Command::Increment];
+        let mut machine: Machine<&[u8], Vec<u8>> =
+            Machine::new(&instructions, MachineConfig::default()).unwrap();
+
+        assert_eq!(machine.run(), Ok(()));
+        assert_eq!(machine.step(), Ok(StepOutcome::Halted));
+        assert_eq!(machine.instruction_pointer(), 1);
+        assert_eq!(machine.cell(machine.cell_pointer()), Some(1));
+    }
+
+    #[test]
+    fn increment_pointer_should_respect_configured_tape() {
+        let instructions = [Command::IncrementPointer, Command::IncrementPointer];
+        let config = MachineConfig::default().tape(2);
+        let mut machine: Machine<&[u8], Vec<u8>> = Machine::new(&instructions, config).unwrap();
+
+        assert_eq!(
+            machine
+                .step()
+                .map_err(|error| (error.kind, error.position.instruction_pointer)),
+            Err((MachineErrorKind::PointerIncrementOutOfBound, 1))
+        );
+    }
+
+    #[test]
+    fn machine_should_need_at_least_one_cell() {
+        let instructions = [Command::Increment];
+        let config = MachineConfig::default().tape(0);
+        let machine: Result<Machine<&[u8], Vec<u8>>, MachineError> =
+            Machine::new(&instructions, config);
+
+        assert_eq!(
+            machine.err().map(|error| error.kind),
+            Some(MachineErrorKind::EmptyTape)
+        );
+    }
+
+    #[test]
+    fn overflow_policy_should_decide_value_past_the_bounds() {
+        for (overflow, increment, decrement) in [
+            (Overflow::Wrap, Ok(0), Ok(255)),
+            (Overflow::Saturate, Ok(255), Ok(0)),
+            (
+                Overflow::Error,
+                Err(MachineErrorKind::CellOverflow),
+                Err(MachineErrorKind::CellUnderflow),
+            ),
+        ] {
+            let config = MachineConfig::default().overflow(overflow);
+            for (instructions, value, expected) in [
+                ([Command::Increment], 255, increment),
+                ([Command::Decrement], 0, decrement),
+            ] {
+                let mut machine: Machine<&[u8], Vec<u8>> =
+                    Machine::new(&instructions, config).unwrap();
+                machine.cells[0] = value;
+
+                assert_eq!(
+                    machine
+                        .step()
+                        .map(|_| machine.cells[0])
+                        .map_err(|error| error.kind),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn wide_cells_should_overflow_at_their_width() {
+        for (width, max) in [
+            (CellWidth::U8, 255),
+            (CellWidth::U16, 65_535),
+            (CellWidth::U32, u32::MAX),
+        ] {
+            let instructions = [Command::Increment];
+            let config = MachineConfig::default().width(width);
+            let mut machine: Machine<&[u8], Vec<u8>> = Machine::new(&instructions, config).unwrap();
+            machine.cells[0] = max - 1;
+
+            assert_eq!(machine.step().map(|_| machine.cells[0]), Ok(max));
+
+            machine.counter = 0;
+            assert_eq!(
+                machine.step().map_err(|error| error.kind),
+                Err(MachineErrorKind::CellOverflow)
+            );
+        }
+    }
+
+    #[test]
+    fn wide_cells_should_write_their_low_byte() {
+        let instructions = [Command::Read, Command::Decrement, Command::Write];
+        let config = MachineConfig::default().width(CellWidth::U16);
+        let input: &[u8] = &[0];
+        let mut output: Vec<u8> = vec![];
+        {
+            let mut machine: Machine<&[u8], &mut Vec<u8>> = Machine::io(
+                &instructions,
+                Box::new(input),
+                Box::new(&mut output),
+                config.overflow(Overflow::Wrap),
+            )
+            .unwrap();
+
+            assert_eq!(machine.run(), Ok(()));
+            assert_eq!(machine.cell(0), Some(65_535));
+        }
+        assert_eq!(output, vec![255]);
+    }
+
+    #[test]
+    fn unbounded_tape_should_grow_in_both_directions() {
+        let instructions = [
+            Command::DecrementPointer,
+            Command::Increment,
+            Command::IncrementPointer,
+            Command::IncrementPointer,
+            Command::IncrementPointer,
+            Command::Increment,
+        ];
+        let config = MachineConfig::default().tape(2).unbounded(true);
+        let mut machine: Machine<&[u8], Vec<u8>> = Machine::new(&instructions, config).unwrap();
+
+        assert_eq!(machine.run(), Ok(()));
+        assert_eq!(machine.position().cell_pointer, 2);
+        assert_eq!(
+            format!("{:?}", machine),
+            "<6:[ DecrementPointer Increment IncrementPointer IncrementPointer IncrementPointer Increment ]|2;{(-1,1)(2,1)}>"
+        );
+    }
+
+    #[test]
+    fn eof_policy_should_decide_value_read_after_end_of_input() {
+        for (eof, expected) in [
+            (Eof::Error, Err(MachineErrorKind::NoByteRead)),
+            (Eof::Zero, Ok(0)),
+            (Eof::MinusOne, Ok(255)),
+            (Eof::Unchanged, Ok(7)),
+        ] {
+            let instructions = [Command::Read];
+            let input: &[u8] = &[];
+            let mut machine: Machine<&[u8], Vec<u8>> = Machine::io(
+                &instructions,
+                Box::new(input),
+                Box::new(vec![]),
+                MachineConfig::default().eof(eof),
+            )
+            .unwrap();
+            machine.cells[0] = 7;
+
+            assert_eq!(
+                machine
+                    .step()
+                    .map(|_| machine.cells[0])
+                    .map_err(|error| error.kind),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn machine_should_stop_when_out_of_fuel() {
+        let instructions = [Command::Increment, Command::JumpAhead, Command::JumpBack];
+        let config = MachineConfig::default().fuel(100);
+        let mut machine: Machine<&[u8], Vec<u8>> = Machine::new(&instructions, config).unwrap();
+
+        assert_eq!(
+            machine.run(),
+            Err(MachineError::new(
+                MachineErrorKind::OutOfFuel,
+                Position {
+                    instruction_pointer: 2,
+                    cell_pointer: 0,
+                    value: 1,
+                    steps: 100,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn machine_should_stop_in_the_middle_of_a_folded_run_when_out_of_fuel() {
+        let instructions = [Command::Increment; 5];
+        let config = MachineConfig::default().fuel(3);
+        let mut machine: Machine<&[u8], Vec<u8>> = Machine::new(&instructions, config).unwrap();
+
+        assert_eq!(
+            machine.run(),
+            Err(MachineError::new(
+                MachineErrorKind::OutOfFuel,
+                Position {
+                    instruction_pointer: 3,
+                    cell_pointer: 0,
+                    value: 3,
+                    steps: 3,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn machine_should_stop_after_time_limit() {
+        let instructions = [Command::Increment, Command::JumpAhead, Command::JumpBack];
+        let config = MachineConfig::default().time_limit(Duration::from_millis(10));
+        let mut machine: Machine<&[u8], Vec<u8>> = Machine::new(&instructions, config).unwrap();
+
+        if let Err(error) = machine.run() {
+            assert_eq!(error.kind, MachineErrorKind::TimedOut);
+            assert!(error.position.steps % CLOCK_INTERVAL == 0);
+            assert!(error.position.steps > 0);
+        } else {
+            assert!(false);
+        }
+    }
+
+    fn outcome(source: &[u8], input: &[u8], config: MachineConfig) -> (String, Vec<u8>) {
+        let instructions = parse(source).unwrap();
+        let mut output: Vec<u8> = vec![];
+        let result = super::super::io_run(&instructions, input, &mut output, config);
+        (format!("{:?}", result), output)
+    }
+
+    #[test]
+    fn backends_should_behave_like_the_interpreter() {
+        for (source, input, config) in [
+            (
+                &b"++++++[>++++++++<-]>+.+.+."[..],
+                &b""[..],
+                MachineConfig::default(),
+            ),
+            (&b",[.,]"[..], &b"bergen"[..], MachineConfig::default()),
+            (&b"+[>+]"[..], &b""[..], MachineConfig::default().tape(300)),
+            (
+                &b"+[->+<]>++++[-<+>]<[>+<-]-"[..],
+                &b""[..],
+                MachineConfig::default(),
+            ),
+            (
+                &b"-[->+++<]>."[..],
+                &b""[..],
+                MachineConfig::default().overflow(Overflow::Wrap),
+            ),
+            (
+                &b"++[>++<-]>[-]+[]"[..],
+                &b""[..],
+                MachineConfig::default().fuel(1_000),
+            ),
+            (
+                &b"<<+[-<]"[..],
+                &b""[..],
+                MachineConfig::default().unbounded(true),
+            ),
+            (
+                &b"++++++++[>++++++++[>+<>+<-]<-]>>."[..],
+                &b""[..],
+                MachineConfig::default(),
+            ),
+        ] {
+            for backend in [Backend::Bytecode, Backend::Jit] {
+                assert_eq!(
+                    outcome(source, input, config.backend(backend)),
+                    outcome(source, input, config)
+                );
+            }
+        }
+    }
 }
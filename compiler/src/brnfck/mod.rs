@@ -1,15 +1,29 @@
-use std::io::{self, Read, Write};
-use std::ops::Add;
+use std::borrow::Borrow;
+use std::io::{self, BufWriter, Read, Write};
 
 const NEWLINE: [u8;1] = [10];
 
+mod bytecode;
+mod c;
+mod config;
+mod error;
+pub mod ir;
+#[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+mod jit;
 mod machine;
 pub mod parser;
 
-pub use self::machine::{Command, MachineError};
+pub use self::c::to_c;
+pub use self::config::{Backend, CellWidth, Eof, MachineConfig, Overflow};
+pub use self::error::{MachineError, MachineErrorKind, Position};
+pub use self::machine::{Command, Machine, StepOutcome};
 
-pub fn run(instructions: &[machine::Command]) -> Result<(), machine::MachineError> {
-    let machine: machine::Machine<&[u8], Vec<u8>> = machine::Machine::new(instructions);
+pub fn run(
+    instructions: &[machine::Command],
+    config: MachineConfig,
+) -> Result<(), MachineError> {
+    let mut machine: machine::Machine<&[u8], Vec<u8>> =
+        machine::Machine::new(instructions, config)?;
 
     machine.run()
 }
@@ -18,40 +32,69 @@ pub fn io_run<I: Read, O: Write>(
     instructions: &[machine::Command],
     input: I,
     output: O,
-) -> Result<(), machine::MachineError> {
+    config: MachineConfig,
+) -> Result<(), MachineError> {
     let input_box = Box::new(input);
     let output_box = Box::new(output);
-    let machine: machine::Machine<I, O> = machine::Machine::io(instructions, input_box, output_box);
+    let mut machine: machine::Machine<I, O> =
+        machine::Machine::io(instructions, input_box, output_box, config)?;
 
     machine.run()
 }
 
-pub fn to_brnfck<O: Write>(
+pub fn to_brnfck<C, O>(instructions: C, output: O) -> Result<(), io::Error>
+where
+    C: IntoIterator,
+    C::Item: Borrow<machine::Command>,
+    O: Write,
+{
+    let mut output = BufWriter::new(output);
+    for instruction in instructions {
+        write!(output, "{}", instruction.borrow().to_brnfck())?;
+    }
+
+    output.flush()
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Spacing {
+    pub loops: usize,
+    pub plains: bool,
+}
+
+pub fn to_bergen<O: Write>(
     instructions: &[machine::Command],
+    spacing: Spacing,
     mut output: O,
 ) -> Result<(), io::Error> {
-    let mut program = String::new();
+    let (mut top, mut middle, mut bottom) = (String::new(), String::new(), String::new());
+    let ground = if spacing.plains { "_" } else { " " };
     for instruction in instructions {
-        program.push(instruction.to_brnfck())
+        if *instruction == Command::JumpBack {
+            space(spacing.loops, ground, &mut top, &mut middle, &mut bottom);
+        }
+        top += instruction.top();
+        middle += instruction.middle();
+        bottom += instruction.bottom();
+        if *instruction == Command::JumpAhead {
+            space(spacing.loops, ground, &mut top, &mut middle, &mut bottom);
+        }
     }
 
-    output.write_all(program.as_bytes())
+    output.write_all(top.as_bytes())?;
+    output.write_all(&NEWLINE)?;
+    output.write_all(middle.as_bytes())?;
+    output.write_all(&NEWLINE)?;
+    output.write_all(bottom.as_bytes())?;
+    output.write_all(&NEWLINE)
 }
 
-pub fn to_bergen<O: Write>(instructions: &[machine::Command], mut output: O) -> Result<(), io::Error> {
-	let (mut top, mut middle, mut bottom) = (String::new(), String::new(), String::new());
-	for instruction in instructions {
-		top = top + instruction.top();
-		middle = middle + instruction.middle();
-		bottom = bottom + instruction.bottom();
-	}
-
-	output.write_all(top.as_bytes())?;
-	output.write_all(&NEWLINE)?;
-	output.write_all(middle.as_bytes())?;
-	output.write_all(&NEWLINE)?;
-	output.write_all(bottom.as_bytes())?;
-	output.write_all(&NEWLINE)
+fn space(columns: usize, ground: &str, top: &mut String, middle: &mut String, bottom: &mut String) {
+    for _ in 0..columns {
+        top.push(' ');
+        middle.push(' ');
+        bottom.push_str(ground);
+    }
 }
 
 pub fn program_from(characters: &[u8]) -> Vec<Command> {
@@ -79,7 +122,7 @@ pub fn program_from(characters: &[u8]) -> Vec<Command> {
 }
 
 fn change_to_character_by(n: usize, factors: &[i16], command: Command, program : &mut Vec<Command>)  {
-    if factors.len() == 0 {
+    if factors.is_empty() {
         alter_to_left(n, command, program);
     } else {
         program.push(Command::IncrementPointer);
@@ -112,7 +155,7 @@ fn factors_of(mut n: i16) -> Vec<i16> {
     while divisor <= n {
         while n % divisor == 0 {
             factors.push(divisor);
-            n = n / divisor;
+            n /= divisor;
         }
         divisor += 1;
     }
@@ -133,7 +176,7 @@ mod tests {
             Command::JumpBack,
         ];
 
-        assert_eq!(run(&instructions), Ok(()));
+        assert_eq!(run(&instructions, MachineConfig::default()), Ok(()));
     }
 
     #[test]
@@ -142,10 +185,22 @@ mod tests {
         let input: &[u8] = "a".as_bytes();
         let mut output: Vec<u8> = vec![];
 
-        assert_eq!(io_run(&instructions, input, &mut output), Ok(()));
+        assert_eq!(
+            io_run(&instructions, input, &mut output, MachineConfig::default()),
+            Ok(())
+        );
         assert_eq!(output, vec!(97));
     }
 
+    #[test]
+    fn to_brnfck_from_iterator() {
+        let instructions = vec![Command::Increment, Command::JumpAhead, Command::JumpBack];
+        let mut output: Vec<u8> = vec![];
+
+        assert!(to_brnfck(instructions.into_iter(), &mut output).is_ok());
+        assert_eq!(output, "+[]".as_bytes());
+    }
+
     #[test]
     fn determine_prime_factors() {
         assert_eq!(factors_of(2), vec![2]);
@@ -154,4 +209,45 @@ mod tests {
         assert_eq!(factors_of(5), vec![5]);
         assert_eq!(factors_of(6), vec![2, 3]);
      }
+
+    #[test]
+    fn to_bergen_with_gap_around_loop_body() {
+        let instructions = [Command::JumpAhead, Command::Write, Command::JumpBack];
+        let spacing = Spacing {
+            loops: 1,
+            plains: true,
+        };
+        let mut output: Vec<u8> = vec![];
+
+        assert!(to_bergen(&instructions, spacing, &mut output).is_ok());
+        assert_eq!(
+            String::from_utf8(output.clone()).unwrap(),
+            "  /\\            /\\  \n /  \\/\\      /\\/  \\ \n/      \\_/\\_/      \\\n"
+        );
+        assert_eq!(crate::parser::parse(&output), Ok(instructions.to_vec()));
+    }
+
+    #[test]
+    fn run_cat_program_until_end_of_input() {
+        let instructions = [
+            Command::Read,
+            Command::JumpAhead,
+            Command::Write,
+            Command::Read,
+            Command::JumpBack,
+        ];
+        let input: &[u8] = "cat".as_bytes();
+        let mut output: Vec<u8> = vec![];
+
+        assert_eq!(
+            io_run(
+                &instructions,
+                input,
+                &mut output,
+                MachineConfig::default().eof(Eof::Zero)
+            ),
+            Ok(())
+        );
+        assert_eq!(output, "cat".as_bytes());
+    }
 }
@@ -1,7 +1,10 @@
 use crate::brnfck::Command;
+use std::error;
+use std::fmt::{self, Display, Formatter};
 
 pub fn parse(source: &[u8]) -> Result<Vec<Command>, ParseError> {
     let mut program = Vec::new();
+    let mut open = Vec::new();
     let mut index = 0;
     while index < source.len() {
         match source[index] {
@@ -11,8 +14,16 @@ pub fn parse(source: &[u8]) -> Result<Vec<Command>, ParseError> {
             46 /* . */ => program.push(Command::Write),
             60 /* < */ => program.push(Command::DecrementPointer),
             62 /* > */ => program.push(Command::IncrementPointer),
-            91 /* [ */ => program.push(Command::JumpAhead),
-            93 /* ] */ => program.push(Command::JumpBack),
+            91 /* [ */ => {
+                open.push(index);
+                program.push(Command::JumpAhead)
+            },
+            93 /* ] */ => {
+                if open.pop().is_none() {
+                    return Err(ParseError::UnmatchedJumpBack(index));
+                }
+                program.push(Command::JumpBack)
+            },
             10 /* LF */ => { /* Accept, but ignore */},
             13 /* CR */ => { /* Accept, but ignore */},
             32 /* SPACE */ => { /* Accept, but ignore */ },
@@ -20,13 +31,43 @@ pub fn parse(source: &[u8]) -> Result<Vec<Command>, ParseError> {
         }
         index += 1;
     }
+    if let Some(index) = open.first() {
+        return Err(ParseError::UnmatchedJumpAhead(*index));
+    }
     Ok(program)
 }
 
+#[derive(Debug, PartialEq, Eq)]
 pub enum ParseError {
     UnknownCharacter(u8),
+    UnmatchedJumpAhead(usize),
+    UnmatchedJumpBack(usize),
+}
+
+impl ParseError {
+    pub fn describe(&self) -> String {
+        match self {
+            ParseError::UnknownCharacter(character) => {
+                format!("unknown character `{}`", *character as char)
+            }
+            ParseError::UnmatchedJumpAhead(index) => {
+                format!("unmatched `[` at character {}", index + 1)
+            }
+            ParseError::UnmatchedJumpBack(index) => {
+                format!("unmatched `]` at character {}", index + 1)
+            }
+        }
+    }
 }
 
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.describe())
+    }
+}
+
+impl error::Error for ParseError {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -36,7 +77,7 @@ mod tests {
     fn should_parse_Increment() {
         let source = "+".as_bytes();
 
-        if let Ok(program) = parse(&source) {
+        if let Ok(program) = parse(source) {
             assert_eq!(program.len(), 1);
             assert_eq!(program, vec!(Command::Increment));
         } else {
@@ -49,7 +90,7 @@ mod tests {
     fn should_parse_Decrement() {
         let source = "-".as_bytes();
 
-        if let Ok(program) = parse(&source) {
+        if let Ok(program) = parse(source) {
             assert_eq!(program.len(), 1);
             assert_eq!(program, vec!(Command::Decrement));
         } else {
@@ -62,7 +103,7 @@ mod tests {
     fn should_parse_IncrementPointer() {
         let source = ">".as_bytes();
 
-        if let Ok(program) = parse(&source) {
+        if let Ok(program) = parse(source) {
             assert_eq!(program.len(), 1);
             assert_eq!(program, vec!(Command::IncrementPointer));
         } else {
@@ -75,7 +116,7 @@ mod tests {
     fn should_parse_DecrementPointer() {
         let source = "<".as_bytes();
 
-        if let Ok(program) = parse(&source) {
+        if let Ok(program) = parse(source) {
             assert_eq!(program.len(), 1);
             assert_eq!(program, vec!(Command::DecrementPointer));
         } else {
@@ -86,11 +127,11 @@ mod tests {
     #[test]
     #[allow(non_snake_case)]
     fn should_parse_JumpAhead() {
-        let source = "[".as_bytes();
+        let source = "[]".as_bytes();
 
-        if let Ok(program) = parse(&source) {
-            assert_eq!(program.len(), 1);
-            assert_eq!(program, vec!(Command::JumpAhead));
+        if let Ok(program) = parse(source) {
+            assert_eq!(program.len(), 2);
+            assert_eq!(program[0], Command::JumpAhead);
         } else {
             assert!(false);
         }
@@ -99,11 +140,11 @@ mod tests {
     #[test]
     #[allow(non_snake_case)]
     fn should_parse_JumpBack() {
-        let source = "]".as_bytes();
+        let source = "[]".as_bytes();
 
-        if let Ok(program) = parse(&source) {
-            assert_eq!(program.len(), 1);
-            assert_eq!(program, vec!(Command::JumpBack));
+        if let Ok(program) = parse(source) {
+            assert_eq!(program.len(), 2);
+            assert_eq!(program[1], Command::JumpBack);
         } else {
             assert!(false);
         }
@@ -114,7 +155,7 @@ mod tests {
     fn should_parse_Write() {
         let source = ".".as_bytes();
 
-        if let Ok(program) = parse(&source) {
+        if let Ok(program) = parse(source) {
             assert_eq!(program.len(), 1);
             assert_eq!(program, vec!(Command::Write));
         } else {
@@ -127,7 +168,7 @@ mod tests {
     fn should_parse_Read() {
         let source = ",".as_bytes();
 
-        if let Ok(program) = parse(&source) {
+        if let Ok(program) = parse(source) {
             assert_eq!(program.len(), 1);
             assert_eq!(program, vec!(Command::Read));
         } else {
@@ -140,7 +181,7 @@ mod tests {
     fn should_parse_program() {
         let source = "++[-]".as_bytes();
 
-        if let Ok(program) = parse(&source) {
+        if let Ok(program) = parse(source) {
             assert_eq!(program.len(), 5);
             assert_eq!(program, vec!(Command::Increment, Command::Increment, Command::JumpAhead, Command::Decrement, Command::JumpBack));
         } else {
@@ -153,11 +194,25 @@ mod tests {
     fn should_parse_program_wuth_whitespace() {
         let source = "++ [ - ] ".as_bytes();
 
-        if let Ok(program) = parse(&source) {
+        if let Ok(program) = parse(source) {
             assert_eq!(program.len(), 5);
             assert_eq!(program, vec!(Command::Increment, Command::Increment, Command::JumpAhead, Command::Decrement, Command::JumpBack));
         } else {
             assert!(false);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn should_report_unmatched_jump_ahead() {
+        let source = "+[[-]".as_bytes();
+
+        assert_eq!(parse(source), Err(ParseError::UnmatchedJumpAhead(1)));
+    }
+
+    #[test]
+    fn should_report_unmatched_jump_back() {
+        let source = "+[-]]".as_bytes();
+
+        assert_eq!(parse(source), Err(ParseError::UnmatchedJumpBack(4)));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::brnfck::{self, MachineError};
+use crate::parser::ParseError;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    Parse(ParseError),
+    Brnfck(brnfck::parser::ParseError),
+    Machine(MachineError),
+    Io(io::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::Parse(problem) => problem.fmt(f),
+            Error::Brnfck(problem) => problem.fmt(f),
+            Error::Machine(problem) => problem.fmt(f),
+            Error::Io(problem) => problem.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Parse(problem) => Some(problem),
+            Error::Brnfck(problem) => Some(problem),
+            Error::Machine(problem) => Some(problem),
+            Error::Io(problem) => Some(problem),
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(problem: ParseError) -> Self {
+        Error::Parse(problem)
+    }
+}
+
+impl From<brnfck::parser::ParseError> for Error {
+    fn from(problem: brnfck::parser::ParseError) -> Self {
+        Error::Brnfck(problem)
+    }
+}
+
+impl From<MachineError> for Error {
+    fn from(problem: MachineError) -> Self {
+        Error::Machine(problem)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(problem: io::Error) -> Self {
+        Error::Io(problem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brnfck::{run, MachineConfig};
+    use crate::parser::parse;
+
+    fn interpret(source: &[u8]) -> Result<(), Error> {
+        let instructions = parse(source)?;
+        run(&instructions, MachineConfig::default())?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_combine_parse_and_machine_errors() {
+        let underflow: &[u8] = "      \n /\\/\\ \n/    \\\n".as_bytes();
+        let unknown: &[u8] = "  \n  \n/|\n".as_bytes();
+
+        assert_eq!(
+            interpret(underflow).map_err(|error| error.to_string()),
+            Err("cell underflowed at step 1".to_string())
+        );
+        assert_eq!(
+            interpret(unknown).map_err(|error| error.to_string()),
+            Err("unknown mountain range in band 1".to_string())
+        );
+    }
+}
@@ -1,2 +1,5 @@
 pub mod brnfck;
+mod error;
 pub mod parser;
+
+pub use self::error::Error;
@@ -0,0 +1,183 @@
+use crate::brnfck::Command;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+
+pub const START: usize = 0;
+
+pub type Symbol = [u8; 3];
+
+pub struct Automaton {
+    alphabet: Vec<Symbol>,
+    transitions: Vec<Vec<Option<usize>>>,
+    accepting: Vec<Option<Command>>,
+}
+
+impl Automaton {
+    pub fn new() -> Automaton {
+        let mut alphabet: Vec<Symbol> = vec![];
+        for command in Command::ALL.iter() {
+            for symbol in glyph(*command) {
+                if !alphabet.contains(&symbol) {
+                    alphabet.push(symbol);
+                }
+            }
+        }
+
+        let mut trie = Automaton {
+            transitions: vec![vec![None; alphabet.len()]],
+            accepting: vec![None],
+            alphabet,
+        };
+        for command in Command::ALL.iter() {
+            let mut state = START;
+            for symbol in glyph(*command) {
+                let symbol = trie.symbol(symbol).unwrap();
+                state = match trie.transitions[state][symbol] {
+                    Some(next) => next,
+                    None => {
+                        trie.transitions.push(vec![None; trie.alphabet.len()]);
+                        trie.accepting.push(None);
+                        let next = trie.transitions.len() - 1;
+                        trie.transitions[state][symbol] = Some(next);
+                        next
+                    }
+                };
+            }
+            trie.accepting[state] = Some(*command);
+        }
+        trie.renumbered()
+    }
+
+    pub fn symbol(&self, column: Symbol) -> Option<usize> {
+        self.alphabet.iter().position(|symbol| *symbol == column)
+    }
+
+    pub fn step(&self, state: usize, symbol: usize) -> Option<usize> {
+        self.transitions[state][symbol]
+    }
+
+    pub fn accepts(&self, state: usize) -> Option<Command> {
+        self.accepting[state]
+    }
+
+    pub fn to_dot<O: Write>(&self, mut output: O) -> Result<(), io::Error> {
+        writeln!(output, "digraph {{")?;
+        writeln!(output, "  node [shape=circle];")?;
+        for (state, accepting) in self.accepting.iter().enumerate() {
+            if let Some(command) = accepting {
+                writeln!(
+                    output,
+                    "  {} [shape=doublecircle,xlabel=\"{}\"];",
+                    state,
+                    command.to_brnfck()
+                )?;
+            }
+        }
+        for (state, transitions) in self.transitions.iter().enumerate() {
+            for (symbol, next) in transitions.iter().enumerate() {
+                if let Some(next) = next {
+                    writeln!(
+                        output,
+                        "  {} -> {} [label=\"{}\"];",
+                        state,
+                        next,
+                        letter(symbol)
+                    )?;
+                }
+            }
+        }
+        writeln!(output, "}}")
+    }
+
+    fn renumbered(self) -> Automaton {
+        let mut order = vec![START];
+        let mut queue = VecDeque::from(vec![START]);
+        while let Some(state) = queue.pop_front() {
+            for next in self.transitions[state].iter().flatten() {
+                order.push(*next);
+                queue.push_back(*next);
+            }
+        }
+        let mut number = vec![0; order.len()];
+        for (index, state) in order.iter().enumerate() {
+            number[*state] = index;
+        }
+        Automaton {
+            transitions: order
+                .iter()
+                .map(|state| {
+                    self.transitions[*state]
+                        .iter()
+                        .map(|next| next.map(|next| number[next]))
+                        .collect()
+                })
+                .collect(),
+            accepting: order.iter().map(|state| self.accepting[*state]).collect(),
+            alphabet: self.alphabet,
+        }
+    }
+}
+
+impl Default for Automaton {
+    fn default() -> Self {
+        Automaton::new()
+    }
+}
+
+fn glyph(command: Command) -> Vec<Symbol> {
+    let (top, middle, bottom) = (
+        command.top().as_bytes(),
+        command.middle().as_bytes(),
+        command.bottom().as_bytes(),
+    );
+    (0..bottom.len())
+        .map(|column| [top[column], middle[column], bottom[column]])
+        .collect()
+}
+
+fn letter(symbol: usize) -> char {
+    (b'a' + symbol as u8) as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accept(automaton: &Automaton, columns: &[Symbol]) -> Option<Command> {
+        let mut state = START;
+        for column in columns {
+            state = automaton.step(state, automaton.symbol(*column)?)?;
+        }
+        automaton.accepts(state)
+    }
+
+    #[test]
+    fn should_accept_every_mountain_range() {
+        let automaton = Automaton::new();
+
+        for command in Command::ALL.iter() {
+            assert_eq!(accept(&automaton, &glyph(*command)), Some(*command));
+        }
+    }
+
+    #[test]
+    fn should_derive_six_letter_alphabet() {
+        let automaton = Automaton::new();
+
+        assert_eq!(automaton.alphabet.len(), 6);
+        assert_eq!(automaton.symbol(*b"  /"), Some(0));
+        assert_eq!(automaton.symbol(*b"  \\"), Some(5));
+    }
+
+    #[test]
+    fn should_generate_states_dot() {
+        let mut output: Vec<u8> = vec![];
+
+        Automaton::new().to_dot(&mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            include_str!("../../../states.dot")
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use super::{normalise, ParseError, Span};
+use std::collections::VecDeque;
+use std::ops::Range;
+
+const NEWLINE: u8 = 10u8;
+const SIGIL: u8 = 35u8;
+const SLOPES: [u8; 2] = [47u8, 92u8];
+
+pub struct Band<T> {
+    pub index: usize,
+    pub line: usize,
+    pub top: T,
+    pub middle: T,
+    pub bottom: T,
+}
+
+impl<T> Band<T> {
+    pub fn span(&self, rows: Range<usize>, columns: Range<usize>) -> Span {
+        Span {
+            band: self.index,
+            line: self.line,
+            rows,
+            columns,
+        }
+    }
+}
+
+pub struct Bands<I: Iterator> {
+    lines: I,
+    pending: VecDeque<I::Item>,
+    partial: Vec<I::Item>,
+    band: usize,
+    line: usize,
+}
+
+#[derive(PartialEq, Eq)]
+enum Kind {
+    Blank,
+    Sky,
+    Mountain,
+}
+
+impl<I> Bands<I>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    pub fn new(lines: I) -> Bands<I> {
+        Bands {
+            lines,
+            pending: VecDeque::new(),
+            partial: vec![],
+            band: 0,
+            line: 0,
+        }
+    }
+
+    pub fn lines(&mut self) -> &mut I {
+        &mut self.lines
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        (self.band, self.line)
+    }
+
+    pub fn partial(&self) -> &[I::Item] {
+        &self.partial
+    }
+
+    fn kind(&mut self, offset: usize) -> Option<Kind> {
+        while self.pending.len() <= offset {
+            let line = self.lines.next()?;
+            self.pending.push_back(line);
+        }
+        Some(kind(self.pending[offset].as_ref()))
+    }
+
+    fn next_line(&mut self) -> Option<I::Item> {
+        let line = self.pending.pop_front().or_else(|| self.lines.next())?;
+        self.line += 1;
+        Some(line)
+    }
+
+    fn start(&mut self) -> Option<usize> {
+        let mut blanks = 0;
+        while self.kind(blanks) == Some(Kind::Blank) {
+            blanks += 1;
+        }
+        let mut mountains = 0;
+        while self.kind(blanks + mountains) == Some(Kind::Mountain) {
+            mountains += 1;
+        }
+        let first = (0..blanks).find(|&offset| !self.pending[offset].as_ref().is_empty());
+        if mountains == 0 {
+            return first;
+        }
+        let closed = self.kind(blanks + mountains).is_some();
+        match (3 - mountains % 3) % 3 {
+            0 => Some(blanks),
+            1 => Some(blanks - 1),
+            _ => match first {
+                Some(offset) if !closed && offset + 3 > blanks + mountains => Some(offset),
+                _ => Some(blanks.saturating_sub(2)),
+            },
+        }
+    }
+
+    fn rows(&mut self) -> Result<Band<I::Item>, ParseError> {
+        let index = self.band;
+        let line = self.line;
+        let mut rows = vec![];
+        while rows.len() < 3 {
+            if let Some(row) = self.next_line() {
+                rows.push(row);
+            } else {
+                let span = Span {
+                    band: index,
+                    line,
+                    rows: rows.len()..rows.len() + 1,
+                    columns: 0..0,
+                };
+                self.partial = rows;
+                return Err(ParseError::NotEnoughRows(span));
+            }
+        }
+        self.band += 1;
+        let bottom = rows.pop().unwrap();
+        let middle = rows.pop().unwrap();
+        let top = rows.pop().unwrap();
+        Ok(Band {
+            index,
+            line,
+            top,
+            middle,
+            bottom,
+        })
+    }
+}
+
+impl<I> Iterator for Bands<I>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    type Item = Result<Band<I::Item>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.kind(0)? {
+                Kind::Mountain => break,
+                Kind::Sky => {
+                    self.next_line();
+                }
+                Kind::Blank => {
+                    let skipped = match self.start() {
+                        Some(0) => break,
+                        Some(offset) => offset,
+                        None => 1,
+                    };
+                    for _ in 0..skipped {
+                        self.next_line();
+                    }
+                }
+            }
+        }
+        Some(self.rows())
+    }
+}
+
+fn kind(line: &[u8]) -> Kind {
+    if line.iter().all(u8::is_ascii_whitespace) {
+        Kind::Blank
+    } else if is_sky(line) {
+        Kind::Sky
+    } else {
+        Kind::Mountain
+    }
+}
+
+fn is_sky(line: &[u8]) -> bool {
+    line.first() == Some(&SIGIL) || !line.iter().any(|byte| SLOPES.contains(byte))
+}
+
+pub fn lines(source: &[u8]) -> Lines<'_> {
+    let source = if source.last() == Some(&NEWLINE) {
+        &source[..source.len() - 1]
+    } else {
+        source
+    };
+    Lines {
+        source: Some(source),
+        first: true,
+    }
+}
+
+pub struct Lines<'a> {
+    source: Option<&'a [u8]>,
+    first: bool,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let source = self.source?;
+        let line = if let Some(index) = source.iter().position(|&byte| byte == NEWLINE) {
+            self.source = Some(&source[index + 1..]);
+            &source[..index]
+        } else {
+            self.source = None;
+            source
+        };
+        let first = self.first;
+        self.first = false;
+        Some(normalise::line(line, first))
+    }
+}
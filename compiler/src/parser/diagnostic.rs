@@ -0,0 +1,111 @@
+use super::band::lines;
+use super::Span;
+use std::fmt::Write;
+
+pub const ROW_NAMES: [&str; 3] = ["top", "middle", "bottom"];
+
+const CONTEXT: usize = 30;
+const ELLIPSIS: &str = "...";
+
+pub fn render(source: &[u8], span: &Span, message: &str, help: &[String]) -> String {
+    let rows: Vec<&[u8]> = lines(source).skip(span.line).take(3).collect();
+    render_rows(&rows, span, message, help)
+}
+
+pub fn render_rows(rows: &[&[u8]], span: &Span, message: &str, help: &[String]) -> String {
+    let first = span.columns.start.saturating_sub(CONTEXT);
+    let last = span.columns.end + CONTEXT;
+    let prefix = if first > 0 { ELLIPSIS } else { "" };
+    let gutter = (span.line + rows.len()).to_string().len();
+
+    let mut output = String::new();
+    writeln!(output, "error: {}", message).unwrap();
+    writeln!(
+        output,
+        "{:>gutter$}--> band {}, line {}, {}",
+        "",
+        span.band + 1,
+        span.line + span.rows.start + 1,
+        columns(span),
+        gutter = gutter
+    )
+    .unwrap();
+    writeln!(output, "{:>gutter$} |", "", gutter = gutter).unwrap();
+    for (offset, row) in rows.iter().enumerate() {
+        let end = last.min(row.len());
+        let start = first.min(end);
+        let suffix = if end < row.len() { ELLIPSIS } else { "" };
+        writeln!(
+            output,
+            "{:>gutter$} | {}{}{}",
+            span.line + offset + 1,
+            prefix,
+            String::from_utf8_lossy(&row[start..end]),
+            suffix,
+            gutter = gutter
+        )
+        .unwrap();
+    }
+    writeln!(
+        output,
+        "{:>gutter$} | {}{}{}",
+        "",
+        " ".repeat(prefix.len()),
+        " ".repeat(span.columns.start - first),
+        "^".repeat(span.columns.len().max(1)),
+        gutter = gutter
+    )
+    .unwrap();
+    for line in help {
+        writeln!(output, "{:>gutter$} = help: {}", "", line, gutter = gutter).unwrap();
+    }
+    output
+}
+
+fn columns(span: &Span) -> String {
+    if span.columns.len() > 1 {
+        format!("columns {}-{}", span.columns.start + 1, span.columns.end)
+    } else {
+        format!("column {}", span.columns.start + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_underline_columns_of_span() {
+        let source: &[u8] = "      \n /\\   \n/  \\/ \n".as_bytes();
+        let span = Span {
+            band: 0,
+            line: 0,
+            rows: 0..3,
+            columns: 4..6,
+        };
+
+        assert_eq!(
+            render(source, &span, "unknown mountain range in band 1", &[]),
+            "error: unknown mountain range in band 1\n --> band 1, line 1, columns 5-6\n  |\n1 |       \n2 |  /\\   \n3 | /  \\/ \n  |     ^^\n"
+        );
+    }
+
+    #[test]
+    fn should_elide_columns_far_from_span() {
+        let row = " ".repeat(100);
+        let rows: Vec<&[u8]> = vec![row.as_bytes(), row.as_bytes(), row.as_bytes()];
+        let span = Span {
+            band: 2,
+            line: 8,
+            rows: 1..2,
+            columns: 50..51,
+        };
+
+        let rendered = render_rows(&rows, &span, "problem", &[]);
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        assert_eq!(lines[1], "  --> band 3, line 10, column 51");
+        assert_eq!(lines[3], format!(" 9 | ...{}...", " ".repeat(61)));
+        assert_eq!(lines[6], format!("   | {}^", " ".repeat(33)));
+    }
+}
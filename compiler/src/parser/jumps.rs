@@ -0,0 +1,85 @@
+use super::{ParseError, Span};
+use crate::brnfck::Command;
+
+pub struct Jumps {
+    open: Vec<Span>,
+}
+
+impl Jumps {
+    pub fn new() -> Jumps {
+        Jumps { open: vec![] }
+    }
+
+    pub fn check(&mut self, command: Command, span: Span) -> Result<(Command, Span), ParseError> {
+        match command {
+            Command::JumpAhead => self.open.push(span.clone()),
+            Command::JumpBack if self.open.pop().is_none() => {
+                return Err(ParseError::UnmatchedJumpBack(span))
+            }
+            _ => { /* do nothing */ }
+        }
+        Ok((command, span))
+    }
+
+    pub fn is_open(&self, band: usize) -> bool {
+        self.open.iter().any(|span| span.band == band)
+    }
+
+    pub fn unmatched(&mut self) -> Vec<ParseError> {
+        self.open
+            .drain(..)
+            .map(ParseError::UnmatchedJumpAhead)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(column: usize) -> Span {
+        Span {
+            band: 0,
+            line: 0,
+            rows: 0..3,
+            columns: column..column + 1,
+        }
+    }
+
+    #[test]
+    fn should_match_nested_jumps() {
+        let mut jumps = Jumps::new();
+
+        for (column, command) in [
+            Command::JumpAhead,
+            Command::JumpAhead,
+            Command::JumpBack,
+            Command::JumpBack,
+        ]
+        .iter()
+        .enumerate()
+        {
+            assert_eq!(
+                jumps.check(*command, span(column)),
+                Ok((*command, span(column)))
+            );
+        }
+        assert_eq!(jumps.unmatched(), vec![]);
+    }
+
+    #[test]
+    fn should_report_outermost_unmatched_jump_ahead_first() {
+        let mut jumps = Jumps::new();
+
+        jumps.check(Command::JumpAhead, span(0)).unwrap();
+        jumps.check(Command::JumpAhead, span(1)).unwrap();
+
+        assert_eq!(
+            jumps.unmatched(),
+            vec![
+                ParseError::UnmatchedJumpAhead(span(0)),
+                ParseError::UnmatchedJumpAhead(span(1)),
+            ]
+        );
+    }
+}
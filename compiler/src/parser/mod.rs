@@ -1,128 +1,218 @@
 use super::brnfck::Command;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::ops::Range;
 
-const NEWLINE: u8 = 10u8;
+mod automaton;
+mod band;
+mod diagnostic;
+mod jumps;
+mod normalise;
+mod stream;
+mod suggestion;
+
+use self::band::{lines, Band, Bands};
+use self::jumps::Jumps;
+
+pub use self::automaton::Automaton;
+pub use self::diagnostic::render;
+pub use self::stream::{stream, Commands};
+pub use self::suggestion::{Difference, Suggestion};
+
+const SLASH: u8 = 47u8;
+const BACKSLASH: u8 = 92u8;
+const SPACE: u8 = 32u8;
+const UNDERSCORE: u8 = 95u8;
 
 pub fn parse(source: &[u8]) -> Result<Vec<Command>, ParseError> {
-    rows(source).and_then(|(top, middle, bottom)| {
-        if top.len() != middle.len() || middle.len() != bottom.len() {
-            return Err(ParseError::DifferentNumberOfRows);
-        }
-        let mut program = vec![];
-        let mut column = 0;
-        while column < top.len() {
-            if let Some((command, next_column)) = peek(column, top, middle, bottom) {
-                column = next_column;
-                program.push(command);
-            } else {
-                return Err(ParseError::UnknownMountainRange(column));
-            }
-        }
-        Ok(program)
-    })
+    parse_spanned(source).map(|(program, _)| program)
 }
 
-fn peek(column: usize, top: &[u8], middle: &[u8], bottom: &[u8]) -> Option<(Command, usize)> {
-    if (column + 6) <= top.len() {
-        if &top[column..column + 6] == "  /\\  ".as_bytes()
-            && &middle[column..column + 6] == " /  \\ ".as_bytes()
-            && &bottom[column..column + 6] == "/    \\".as_bytes()
-        {
-            return Some((Command::IncrementPointer, column + 6));
-        }
+pub fn parse_spanned(source: &[u8]) -> Result<(Vec<Command>, Vec<Span>), ParseError> {
+    let (parsed, mut problems) = parse_all(source);
+    if problems.is_empty() {
+        Ok(parsed.into_iter().unzip())
+    } else {
+        Err(problems.remove(0))
     }
-    if (column + 8) <= top.len() {
-        if &top[column..column + 8] == "  /\\/\\  ".as_bytes()
-            && &middle[column..column + 8] == " /    \\ ".as_bytes()
-            && &bottom[column..column + 8] == "/      \\".as_bytes()
-        {
-            return Some((Command::DecrementPointer, column + 8));
+}
+
+pub fn parse_recovering(source: &[u8]) -> (Vec<Command>, Vec<ParseError>) {
+    let (parsed, problems) = parse_all(source);
+    (
+        parsed.into_iter().map(|(command, _)| command).collect(),
+        problems,
+    )
+}
+
+fn parse_all(source: &[u8]) -> (Vec<(Command, Span)>, Vec<ParseError>) {
+    let automaton = Automaton::new();
+    let mut jumps = Jumps::new();
+    let mut parsed = vec![];
+    let mut problems = vec![];
+    let mut emit = |result| match result {
+        Ok(command) => parsed.push(command),
+        Err(problem) => problems.push(problem),
+    };
+    for band in Bands::new(lines(source)) {
+        match band {
+            Ok(band) => parse_band(&automaton, &mut jumps, &band, &mut emit),
+            Err(problem) => emit(Err(problem)),
         }
     }
-    if (column + 4) <= top.len() {
-        if &top[column..column + 4] == "    ".as_bytes()
-            && &middle[column..column + 4] == " /\\ ".as_bytes()
-            && &bottom[column..column + 4] == "/  \\".as_bytes()
-        {
-            return Some((Command::Increment, column + 4));
-        }
+    for problem in jumps.unmatched() {
+        emit(Err(problem));
     }
-    if (column + 6) <= top.len() {
-        if &top[column..column + 6] == "      ".as_bytes()
-            && &middle[column..column + 6] == " /\\/\\ ".as_bytes()
-            && &bottom[column..column + 6] == "/    \\".as_bytes()
-        {
-            return Some((Command::Decrement, column + 6));
+    (parsed, problems)
+}
+
+fn parse_band<T, F>(automaton: &Automaton, jumps: &mut Jumps, band: &Band<T>, emit: &mut F)
+where
+    T: AsRef<[u8]>,
+    F: FnMut(Result<(Command, Span), ParseError>),
+{
+    let (top, middle, bottom) = (
+        normalise::row(band.top.as_ref()),
+        normalise::row(band.middle.as_ref()),
+        normalise::row(band.bottom.as_ref()),
+    );
+    let width = top.len().max(middle.len()).max(bottom.len());
+    let mut state = automaton::START;
+    let mut start = 0;
+    let mut column = 0;
+    while column < width {
+        let symbol = [
+            normalise::cell(top, column),
+            normalise::cell(middle, column),
+            normalise::cell(bottom, column),
+        ];
+        if state == automaton::START && is_ground(symbol) {
+            column += 1;
+            start = column;
+            continue;
         }
-    }
-    if (column + 8) <= top.len() {
-        if &top[column..column + 8] == "  /\\    ".as_bytes()
-            && &middle[column..column + 8] == " /  \\/\\ ".as_bytes()
-            && &bottom[column..column + 8] == "/      \\".as_bytes()
-        {
-            return Some((Command::JumpAhead, column + 8));
+        let next_state = automaton
+            .symbol(symbol)
+            .and_then(|symbol| automaton.step(state, symbol));
+        if let Some(next_state) = next_state {
+            column += 1;
+            if let Some(command) = automaton.accepts(next_state) {
+                emit(jumps.check(command, band.span(0..3, start..column)));
+                state = automaton::START;
+                start = column;
+            } else {
+                state = next_state;
+            }
+        } else {
+            column = resync(start, bottom, width);
+            emit(Err(ParseError::UnknownMountainRange(
+                band.span(0..3, start..column),
+                suggestion::suggestions(start, [top, middle, bottom]),
+            )));
+            state = automaton::START;
+            start = column;
         }
     }
-    if (column + 8) <= top.len() {
-        if &top[column..column + 8] == "    /\\  ".as_bytes()
-            && &middle[column..column + 8] == " /\\/  \\ ".as_bytes()
-            && &bottom[column..column + 8] == "/      \\".as_bytes()
-        {
-            return Some((Command::JumpBack, column + 8));
+    if state != automaton::START {
+        emit(Err(ParseError::UnknownMountainRange(
+            band.span(0..3, start..width),
+            suggestion::suggestions(start, [top, middle, bottom]),
+        )));
+    }
+}
+
+fn is_ground(symbol: [u8; 3]) -> bool {
+    symbol[0] == SPACE && symbol[1] == SPACE && (symbol[2] == SPACE || symbol[2] == UNDERSCORE)
+}
+
+fn resync(column: usize, bottom: &[u8], width: usize) -> usize {
+    let mut next_column = column + 1;
+    while next_column < width
+        && !(normalise::cell(bottom, next_column) == SLASH
+            && [BACKSLASH, SPACE, UNDERSCORE].contains(&normalise::cell(bottom, next_column - 1)))
+    {
+        next_column += 1;
+    }
+    next_column
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Span {
+    pub band: usize,
+    pub line: usize,
+    pub rows: Range<usize>,
+    pub columns: Range<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    NotEnoughRows(Span),
+    UnknownMountainRange(Span, Vec<Suggestion>),
+    UnmatchedJumpAhead(Span),
+    UnmatchedJumpBack(Span),
+    Unreadable(Span, io::ErrorKind),
+}
+
+impl ParseError {
+    pub fn span(&self) -> &Span {
+        match self {
+            ParseError::NotEnoughRows(span) => span,
+            ParseError::UnknownMountainRange(span, _) => span,
+            ParseError::UnmatchedJumpAhead(span) => span,
+            ParseError::UnmatchedJumpBack(span) => span,
+            ParseError::Unreadable(span, _) => span,
         }
     }
-    if (column + 2) <= top.len() {
-        if &top[column..column + 2] == "  ".as_bytes()
-            && &middle[column..column + 2] == "  ".as_bytes()
-            && &bottom[column..column + 2] == "/\\".as_bytes()
-        {
-            return Some((Command::Write, column + 2));
+
+    pub fn render(&self, source: &[u8]) -> String {
+        diagnostic::render(source, self.span(), &self.message(), &self.help())
+    }
+
+    fn message(&self) -> String {
+        match self {
+            ParseError::NotEnoughRows(span) => {
+                format!("band {} has {} of 3 rows", span.band + 1, span.rows.start)
+            }
+            ParseError::UnknownMountainRange(span, _) => {
+                format!("unknown mountain range in band {}", span.band + 1)
+            }
+            ParseError::UnmatchedJumpAhead(span) => {
+                format!("unmatched `[` in band {}", span.band + 1)
+            }
+            ParseError::UnmatchedJumpBack(span) => {
+                format!("unmatched `]` in band {}", span.band + 1)
+            }
+            ParseError::Unreadable(span, kind) => {
+                format!("band {} could not be read: {}", span.band + 1, kind)
+            }
         }
     }
-    if (column + 10) <= top.len() {
-        if &top[column..column + 10] == "  /\\  /\\  ".as_bytes()
-            && &middle[column..column + 10] == " /  \\/  \\ ".as_bytes()
-            && &bottom[column..column + 10] == "/        \\".as_bytes()
-        {
-            return Some((Command::Read, column + 10));
+
+    fn help(&self) -> Vec<String> {
+        match self {
+            ParseError::UnknownMountainRange(_, suggestions) => suggestions
+                .iter()
+                .map(|suggestion| suggestion.describe())
+                .collect(),
+            ParseError::UnmatchedJumpAhead(_) => {
+                vec!["close the loop with a `]` mountain range after it".to_string()]
+            }
+            ParseError::UnmatchedJumpBack(_) => {
+                vec!["open the loop with a `[` mountain range before it".to_string()]
+            }
+            _ => vec![],
         }
     }
-    None
 }
 
-fn rows(source: &[u8]) -> Result<(&[u8], &[u8], &[u8]), ParseError> {
-    let mut index = 0;
-    while index < source.len() && source[index] != NEWLINE {
-        index += 1;
-    }
-    let first_index = index;
-    index += 1;
-    while index < source.len() && source[index] != NEWLINE {
-        index += 1;
-    }
-    let second_index = index;
-    index += 1;
-    while index < source.len() && source[index] != NEWLINE {
-        index += 1;
-    }
-    let third_index = index;
-    if index < source.len() {
-        Ok((
-            &source[0..first_index],
-            &source[first_index + 1..second_index],
-            &source[second_index + 1..third_index],
-        ))
-    } else {
-        Err(ParseError::NotEnoughRows)
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.message())
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub enum ParseError {
-    Unknown,
-    NotEnoughRows,
-    DifferentNumberOfRows,
-    UnknownMountainRange(usize),
-}
+impl error::Error for ParseError {}
 
 #[cfg(test)]
 mod tests {
@@ -192,21 +282,27 @@ mod tests {
     fn should_parse_jump_ahead() {
         let source: &[u8] = "  /\\    \n /  \\/\\ \n/      \\\n".as_bytes();
 
-        if let Ok(instructions) = parse(source) {
-            assert_eq!(instructions.len(), 1);
-            assert_eq!(instructions, vec![Command::JumpAhead])
-        } else {
-            assert!(false);
-        }
+        let (instructions, problems) = parse_recovering(source);
+
+        assert_eq!(instructions, vec![Command::JumpAhead]);
+        assert_eq!(
+            problems,
+            vec![ParseError::UnmatchedJumpAhead(Span {
+                band: 0,
+                line: 0,
+                rows: 0..3,
+                columns: 0..8,
+            })]
+        );
     }
 
     #[test]
     fn should_parse_jump_back() {
-        let source: &[u8] = "    /\\  \n /\\/  \\ \n/      \\\n".as_bytes();
+        let source: &[u8] =
+            "  /\\        /\\  \n /  \\/\\  /\\/  \\ \n/      \\/      \\\n".as_bytes();
 
         if let Ok(instructions) = parse(source) {
-            assert_eq!(instructions.len(), 1);
-            assert_eq!(instructions, vec![Command::JumpBack])
+            assert_eq!(instructions, vec![Command::JumpAhead, Command::JumpBack])
         } else {
             assert!(false);
         }
@@ -249,11 +345,37 @@ mod tests {
     }
 
     #[test]
-    fn should_throw_when_number_of_columns_do_not_agree() {
-        let source: &[u8] = " \n\n\n".as_bytes();
+    fn should_pad_ragged_rows() {
+        let source: &[u8] = "  /\\\n /  \\ \t \n/    \\\n".as_bytes();
+
+        if let Ok(instructions) = parse(source) {
+            assert_eq!(instructions, vec![Command::IncrementPointer]);
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_ignore_carriage_returns_and_byte_order_mark() {
+        let source: &[u8] = "\u{feff}    \r\n /\\ \r\n/  \\\r\n".as_bytes();
+
+        if let Ok(instructions) = parse(source) {
+            assert_eq!(instructions, vec![Command::Increment]);
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_keep_columns_of_original_text() {
+        let source: &[u8] = "\u{feff}  /\\  \r\n /  \\ \r\n/    |\r\n".as_bytes();
 
         if let Err(problem) = parse(source) {
-            assert_eq!(problem, ParseError::DifferentNumberOfRows);
+            assert_eq!(problem.span().columns, 0..6);
+            assert_eq!(
+                problem.render(source),
+                "error: unknown mountain range in band 1\n --> band 1, line 1, columns 1-6\n  |\n1 |   /\\  \n2 |  /  \\ \n3 | /    |\n  | ^^^^^^\n  = help: looks like `>` with `|` instead of `\\` in the bottom row\n"
+            );
         } else {
             assert!(false);
         }
@@ -264,7 +386,348 @@ mod tests {
         let source: &[u8] = " \n\n".as_bytes();
 
         if let Err(problem) = parse(source) {
-            assert_eq!(problem, ParseError::NotEnoughRows);
+            assert_eq!(
+                problem,
+                ParseError::NotEnoughRows(Span {
+                    band: 0,
+                    line: 0,
+                    rows: 2..3,
+                    columns: 0..0,
+                })
+            );
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_parse_bands_in_reading_order() {
+        let source: &[u8] = "    \n /\\ \n/  \\\n      \n /\\/\\ \n/    \\\n".as_bytes();
+
+        if let Ok(instructions) = parse(source) {
+            assert_eq!(instructions, vec![Command::Increment, Command::Decrement])
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_parse_bands_separated_by_blank_lines() {
+        let source: &[u8] = "    \n /\\ \n/  \\\n\n\n  \n  \n/\\".as_bytes();
+
+        if let Ok(instructions) = parse(source) {
+            assert_eq!(instructions, vec![Command::Increment, Command::Write])
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_parse_band_with_stripped_top_row() {
+        let source: &[u8] = "\n /\\ \n/  \\\n".as_bytes();
+
+        assert_eq!(parse(source), Ok(vec![Command::Increment]));
+    }
+
+    #[test]
+    fn should_separate_bands_with_whitespace_only_lines() {
+        let source: &[u8] = "    \n /\\ \n/  \\\n   \n    \n /\\ \n/  \\\n".as_bytes();
+
+        assert_eq!(
+            parse_recovering(source),
+            (vec![Command::Increment, Command::Increment], vec![])
+        );
+    }
+
+    #[test]
+    fn should_parse_stripped_write_band_after_separator() {
+        let source: &[u8] = "    \n /\\ \n/  \\\n\n\n\n/\\\n".as_bytes();
+
+        assert_eq!(parse(source), Ok(vec![Command::Increment, Command::Write]));
+    }
+
+    #[test]
+    fn should_parse_band_with_blank_top_row_directly_followed_by_band() {
+        let source: &[u8] = "    \n /\\ \n/  \\\n  /\\  \n /  \\ \n/    \\\n".as_bytes();
+
+        assert_eq!(
+            parse(source),
+            Ok(vec![Command::Increment, Command::IncrementPointer])
+        );
+    }
+
+    #[test]
+    fn should_report_band_of_unknown_mountain_range() {
+        let source: &[u8] = "    \n /\\ \n/  \\\n\n      \n /\\   \n/  \\/ \n".as_bytes();
+
+        if let Err(problem) = parse(source) {
+            assert_eq!(
+                problem,
+                ParseError::UnknownMountainRange(
+                    Span {
+                        band: 1,
+                        line: 4,
+                        rows: 0..3,
+                        columns: 4..5,
+                    },
+                    vec![Suggestion {
+                        command: Command::Write,
+                        differences: vec![Difference {
+                            row: 2,
+                            column: 5,
+                            expected: 92,
+                            found: 32,
+                        }],
+                    }]
+                )
+            );
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_throw_when_last_band_has_to_few_rows() {
+        let source: &[u8] = "    \n /\\ \n/  \\\n\n    \n /\\ \n".as_bytes();
+
+        if let Err(problem) = parse(source) {
+            assert_eq!(
+                problem,
+                ParseError::NotEnoughRows(Span {
+                    band: 1,
+                    line: 4,
+                    rows: 2..3,
+                    columns: 0..0,
+                })
+            );
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_render_unknown_mountain_range() {
+        let source: &[u8] = "    \n /\\ \n/  \\\n\n      \n /\\   \n/  \\/ \n".as_bytes();
+
+        if let Err(problem) = parse(source) {
+            assert_eq!(
+                problem.render(source),
+                "error: unknown mountain range in band 2\n --> band 2, line 5, column 5\n  |\n5 |       \n6 |  /\\   \n7 | /  \\/ \n  |     ^\n  = help: looks like `.` with a missing `\\` in the bottom row\n"
+            );
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_recover_from_unknown_mountain_ranges() {
+        let source: &[u8] = "            \n /\\  /   /\\ \n/  \\/  \\/  \\\n".as_bytes();
+
+        let (instructions, problems) = parse_recovering(source);
+
+        assert_eq!(instructions, vec![Command::Increment, Command::Increment]);
+        assert_eq!(
+            problems,
+            vec![ParseError::UnknownMountainRange(
+                Span {
+                    band: 0,
+                    line: 0,
+                    rows: 0..3,
+                    columns: 4..8,
+                },
+                vec![Suggestion {
+                    command: Command::Increment,
+                    differences: vec![Difference {
+                        row: 1,
+                        column: 6,
+                        expected: 92,
+                        found: 32,
+                    }],
+                }]
+            )]
+        );
+    }
+
+    #[test]
+    fn should_report_problems_of_every_band() {
+        let source: &[u8] = "   \n /\\\n/  \\\n\n      \n /\\   \n/  \\/ \n\n  \n".as_bytes();
+
+        let (instructions, problems) = parse_recovering(source);
+
+        assert_eq!(instructions, vec![Command::Increment, Command::Increment]);
+        assert_eq!(
+            problems,
+            vec![
+                ParseError::UnknownMountainRange(
+                    Span {
+                        band: 1,
+                        line: 4,
+                        rows: 0..3,
+                        columns: 4..5,
+                    },
+                    vec![Suggestion {
+                        command: Command::Write,
+                        differences: vec![Difference {
+                            row: 2,
+                            column: 5,
+                            expected: 92,
+                            found: 32,
+                        }],
+                    }]
+                ),
+                ParseError::NotEnoughRows(Span {
+                    band: 2,
+                    line: 8,
+                    rows: 1..2,
+                    columns: 0..0,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_throw_when_band_ends_inside_mountain_range() {
+        let source: &[u8] = "  /\\\n /  \n/   \n".as_bytes();
+
+        if let Err(ParseError::UnknownMountainRange(span, suggestions)) = parse(source) {
+            assert_eq!(span.columns, 0..4);
+            assert_eq!(suggestions[0].command, Command::IncrementPointer);
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_ignore_valleys_and_plains_between_mountain_ranges() {
+        let source: &[u8] = "            \n /\\      /\\ \n/  \\ __ /  \\\n".as_bytes();
+
+        if let Ok(instructions) = parse(source) {
+            assert_eq!(instructions, vec![Command::Increment, Command::Increment]);
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_resync_after_valley() {
+        let source: &[u8] = "          \n /\\    /\\ \n/  \\/ /  \\\n".as_bytes();
+
+        let (instructions, problems) = parse_recovering(source);
+
+        assert_eq!(instructions, vec![Command::Increment, Command::Increment]);
+        assert_eq!(problems[0].span().columns, 4..6);
+    }
+
+    #[test]
+    fn should_ignore_sky_rows_above_band() {
+        let source: &[u8] = "#  \\ | /\n   ~~ sun ~~\n    \n /\\ \n/  \\\n".as_bytes();
+
+        if let Ok(instructions) = parse(source) {
+            assert_eq!(instructions, vec![Command::Increment]);
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_ignore_sky_row_with_underscores_above_band() {
+        let source: &[u8] = "step_one\n    \n /\\ \n/  \\\n".as_bytes();
+
+        if let Ok(instructions) = parse(source) {
+            assert_eq!(instructions, vec![Command::Increment]);
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_count_sky_rows_when_reporting_lines() {
+        let source: &[u8] =
+            "plus\n    \n /\\ \n/  \\\n\nwrite\n      \n /\\   \n/  \\/ \n".as_bytes();
+
+        if let Err(problem) = parse(source) {
+            assert_eq!(problem.span().band, 1);
+            assert_eq!(problem.span().line, 6);
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_ignore_annotation_after_last_band() {
+        let source: &[u8] = "  \n  \n/\\\n\nA haiku\nof one mountain".as_bytes();
+
+        if let Ok(instructions) = parse(source) {
+            assert_eq!(instructions, vec![Command::Write]);
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_report_unmatched_jump_ahead() {
+        let source: &[u8] = "  /\\        \n /  \\/\\  /\\ \n/      \\/  \\\n".as_bytes();
+
+        if let Err(problem) = parse(source) {
+            assert_eq!(
+                problem.render(source),
+                "error: unmatched `[` in band 1\n --> band 1, line 1, columns 1-8\n  |\n1 |   /\\        \n2 |  /  \\/\\  /\\ \n3 | /      \\/  \\\n  | ^^^^^^^^\n  = help: close the loop with a `]` mountain range after it\n"
+            );
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn should_report_unmatched_jump_back() {
+        let source: &[u8] = "        /\\  \n /\\  /\\/  \\ \n/  \\/      \\\n".as_bytes();
+
+        let (instructions, problems) = parse_recovering(source);
+
+        assert_eq!(instructions, vec![Command::Increment]);
+        assert_eq!(
+            problems,
+            vec![ParseError::UnmatchedJumpBack(Span {
+                band: 0,
+                line: 0,
+                rows: 0..3,
+                columns: 4..12,
+            })]
+        );
+    }
+
+    #[test]
+    fn should_parse_span_of_every_mountain_range() {
+        let source: &[u8] = "          \n /\\  /\\/\\ \n/  \\/    \\\n\n  \n  \n/\\\n".as_bytes();
+
+        if let Ok((instructions, spans)) = parse_spanned(source) {
+            assert_eq!(
+                instructions,
+                vec![Command::Increment, Command::Decrement, Command::Write]
+            );
+            assert_eq!(
+                spans,
+                vec![
+                    Span {
+                        band: 0,
+                        line: 0,
+                        rows: 0..3,
+                        columns: 0..4,
+                    },
+                    Span {
+                        band: 0,
+                        line: 0,
+                        rows: 0..3,
+                        columns: 4..10,
+                    },
+                    Span {
+                        band: 1,
+                        line: 4,
+                        rows: 0..3,
+                        columns: 0..2,
+                    },
+                ]
+            );
         } else {
             assert!(false);
         }
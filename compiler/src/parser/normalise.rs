@@ -0,0 +1,60 @@
+const BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const CARRIAGE_RETURN: u8 = 13u8;
+const SPACE: u8 = 32u8;
+const TAB: u8 = 9u8;
+
+pub fn line(line: &[u8], first: bool) -> &[u8] {
+    let line = if first && line.starts_with(BOM) {
+        &line[BOM.len()..]
+    } else {
+        line
+    };
+    if line.last() == Some(&CARRIAGE_RETURN) {
+        &line[..line.len() - 1]
+    } else {
+        line
+    }
+}
+
+pub fn row(row: &[u8]) -> &[u8] {
+    let mut length = row.len();
+    while length > 0 && (row[length - 1] == SPACE || row[length - 1] == TAB) {
+        length -= 1;
+    }
+    &row[..length]
+}
+
+pub fn cell(row: &[u8], column: usize) -> u8 {
+    row.get(column).cloned().unwrap_or(SPACE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_strip_byte_order_mark_from_first_line() {
+        assert_eq!(line("\u{feff}/\\".as_bytes(), true), "/\\".as_bytes());
+        assert_eq!(
+            line("\u{feff}/\\".as_bytes(), false),
+            "\u{feff}/\\".as_bytes()
+        );
+    }
+
+    #[test]
+    fn should_strip_carriage_return() {
+        assert_eq!(line(" /\\ \r".as_bytes(), false), " /\\ ".as_bytes());
+    }
+
+    #[test]
+    fn should_strip_trailing_whitespace_from_row() {
+        assert_eq!(row(" /\\ \t ".as_bytes()), " /\\".as_bytes());
+        assert_eq!(row("    ".as_bytes()), "".as_bytes());
+    }
+
+    #[test]
+    fn should_pad_row_with_spaces() {
+        assert_eq!(cell(" /".as_bytes(), 1), 47);
+        assert_eq!(cell(" /".as_bytes(), 2), 32);
+    }
+}
@@ -0,0 +1,263 @@
+use super::automaton::Automaton;
+use super::band::{Band, Bands};
+use super::jumps::Jumps;
+use super::{diagnostic, normalise, parse_band, ParseError, Span};
+use crate::brnfck::Command;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read};
+
+const NEWLINE: u8 = 10u8;
+
+pub fn stream<R: Read>(input: R) -> Commands<R> {
+    Commands {
+        automaton: Automaton::new(),
+        jumps: Jumps::new(),
+        bands: Bands::new(ReadLines {
+            input: BufReader::new(input),
+            problem: None,
+            first: true,
+        }),
+        band: None,
+        open: vec![],
+        parsed: VecDeque::new(),
+        finished: false,
+    }
+}
+
+pub struct Commands<R: Read> {
+    automaton: Automaton,
+    jumps: Jumps,
+    bands: Bands<ReadLines<R>>,
+    band: Option<Band<Vec<u8>>>,
+    open: Vec<Band<Vec<u8>>>,
+    parsed: VecDeque<Result<(Command, Span), ParseError>>,
+    finished: bool,
+}
+
+impl<R: Read> Commands<R> {
+    pub fn next_spanned(&mut self) -> Option<Result<(Command, Span), ParseError>> {
+        while self.parsed.is_empty() && !self.finished {
+            self.read_band();
+        }
+        self.parsed.pop_front()
+    }
+
+    pub fn render(&self, problem: &ParseError) -> String {
+        let rows: Vec<&[u8]> = match self
+            .band
+            .iter()
+            .chain(self.open.iter())
+            .find(|band| band.index == problem.span().band)
+        {
+            Some(band) => vec![&band.top[..], &band.middle[..], &band.bottom[..]],
+            None => match *problem {
+                ParseError::NotEnoughRows(_) => {
+                    self.bands.partial().iter().map(|row| &row[..]).collect()
+                }
+                _ => vec![],
+            },
+        };
+        diagnostic::render_rows(&rows, problem.span(), &problem.message(), &problem.help())
+    }
+
+    fn read_band(&mut self) {
+        let band = self.bands.next();
+        if let Some(kind) = self.bands.lines().problem.take() {
+            let (band, line) = self.bands.position();
+            self.parsed.push_back(Err(ParseError::Unreadable(
+                Span {
+                    band,
+                    line,
+                    rows: 0..0,
+                    columns: 0..0,
+                },
+                kind,
+            )));
+            self.finished = true;
+            return;
+        }
+        match band {
+            Some(Ok(band)) => {
+                let parsed = &mut self.parsed;
+                parse_band(&self.automaton, &mut self.jumps, &band, &mut |command| {
+                    parsed.push_back(command)
+                });
+                if let Some(previous) = self.band.replace(band) {
+                    if self.jumps.is_open(previous.index) {
+                        self.open.push(previous);
+                    }
+                }
+                let jumps = &self.jumps;
+                self.open.retain(|band| jumps.is_open(band.index));
+            }
+            Some(Err(problem)) => self.parsed.push_back(Err(problem)),
+            None => {
+                self.parsed
+                    .extend(self.jumps.unmatched().into_iter().map(Err));
+                self.finished = true;
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for Commands<R> {
+    type Item = Result<Command, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_spanned()
+            .map(|parsed| parsed.map(|(command, _)| command))
+    }
+}
+
+struct ReadLines<R> {
+    input: BufReader<R>,
+    problem: Option<io::ErrorKind>,
+    first: bool,
+}
+
+impl<R: Read> Iterator for ReadLines<R> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = vec![];
+        match self.input.read_until(NEWLINE, &mut line) {
+            Ok(0) => None,
+            Ok(_) => {
+                if line.last() == Some(&NEWLINE) {
+                    line.pop();
+                }
+                let first = self.first;
+                self.first = false;
+                Some(normalise::line(&line, first).to_vec())
+            }
+            Err(error) => {
+                self.problem = Some(error.kind());
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse;
+    use super::*;
+
+    struct Failing;
+
+    impl Read for Failing {
+        fn read(&mut self, _buffer: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "failing"))
+        }
+    }
+
+    #[test]
+    fn should_stream_same_commands_as_parse() {
+        let source: &[u8] =
+            "    \n /\\ \n/  \\\n\n  /\\    \n /  \\/\\ \n/      \\\n      \n /\\/\\ \n/    \\\n"
+                .as_bytes();
+
+        let streamed: Result<Vec<Command>, ParseError> = stream(source).collect();
+
+        assert_eq!(streamed, parse(source));
+    }
+
+    #[test]
+    fn should_stream_band_with_blank_top_row_directly_followed_by_band() {
+        let source: &[u8] = "    \n /\\ \n/  \\\n  /\\  \n /  \\ \n/    \\\n".as_bytes();
+
+        let streamed: Result<Vec<Command>, ParseError> = stream(source).collect();
+
+        assert_eq!(
+            streamed,
+            Ok(vec![Command::Increment, Command::IncrementPointer])
+        );
+    }
+
+    #[test]
+    fn should_stream_normalised_lines() {
+        let source: &[u8] = "\u{feff}    \r\n /\\\r\n/  \\  \r\n".as_bytes();
+
+        let streamed: Result<Vec<Command>, ParseError> = stream(source).collect();
+
+        assert_eq!(streamed, Ok(vec![Command::Increment]));
+    }
+
+    #[test]
+    fn should_stream_commands_before_problem() {
+        let source: &[u8] = "    \n /\\ \n/  \\\n      \n /\\   \n/  \\/ \n".as_bytes();
+        let mut commands = stream(source);
+
+        assert_eq!(commands.next(), Some(Ok(Command::Increment)));
+        assert_eq!(commands.next(), Some(Ok(Command::Increment)));
+        if let Some(Err(problem)) = commands.next() {
+            assert_eq!(
+                commands.render(&problem),
+                "error: unknown mountain range in band 2\n --> band 2, line 4, column 5\n  |\n4 |       \n5 |  /\\   \n6 | /  \\/ \n  |     ^\n  = help: looks like `.` with a missing `\\` in the bottom row\n"
+            );
+        } else {
+            assert!(false);
+        }
+        assert_eq!(commands.next(), None);
+    }
+
+    #[test]
+    fn should_render_rows_of_band_with_too_few_rows() {
+        let source: &[u8] = "    \n /\\ \n/  \\\n\n      \n /\\/\\ \n".as_bytes();
+        let mut commands = stream(source);
+
+        assert_eq!(commands.next(), Some(Ok(Command::Increment)));
+        if let Some(Err(problem)) = commands.next() {
+            assert_eq!(
+                commands.render(&problem),
+                "error: band 2 has 2 of 3 rows\n --> band 2, line 7, column 1\n  |\n5 |       \n6 |  /\\/\\ \n  | ^\n"
+            );
+        } else {
+            assert!(false);
+        }
+        assert_eq!(commands.next(), None);
+    }
+
+    #[test]
+    fn should_report_unreadable_input() {
+        let mut commands = stream(Failing);
+
+        if let Some(Err(problem)) = commands.next() {
+            assert_eq!(
+                problem,
+                ParseError::Unreadable(
+                    Span {
+                        band: 0,
+                        line: 0,
+                        rows: 0..0,
+                        columns: 0..0,
+                    },
+                    io::ErrorKind::BrokenPipe
+                )
+            );
+        } else {
+            assert!(false);
+        }
+        assert_eq!(commands.next(), None);
+    }
+
+    #[test]
+    fn should_stream_unmatched_jump_ahead_at_end_of_input() {
+        let source: &[u8] = "  /\\    \n /  \\/\\ \n/      \\\n\n    \n /\\ \n/  \\\n".as_bytes();
+        let mut commands = stream(source);
+
+        assert_eq!(commands.next(), Some(Ok(Command::JumpAhead)));
+        assert_eq!(commands.next(), Some(Ok(Command::Increment)));
+        if let Some(Err(problem)) = commands.next() {
+            assert_eq!(problem.span().band, 0);
+            assert_eq!(problem.span().columns, 0..8);
+            assert_eq!(
+                commands.render(&problem),
+                "error: unmatched `[` in band 1\n --> band 1, line 1, columns 1-8\n  |\n1 |   /\\    \n2 |  /  \\/\\ \n3 | /      \\\n  | ^^^^^^^^\n  = help: close the loop with a `]` mountain range after it\n"
+            );
+        } else {
+            assert!(false);
+        }
+        assert_eq!(commands.next(), None);
+    }
+}
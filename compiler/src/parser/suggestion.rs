@@ -0,0 +1,143 @@
+use super::diagnostic::ROW_NAMES;
+use crate::brnfck::Command;
+
+const MAX_DIFFERENCES: usize = 2;
+const SPACE: u8 = 32u8;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Suggestion {
+    pub command: Command,
+    pub differences: Vec<Difference>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Difference {
+    pub row: usize,
+    pub column: usize,
+    pub expected: u8,
+    pub found: u8,
+}
+
+impl Suggestion {
+    pub fn describe(&self) -> String {
+        let differences: Vec<String> = self
+            .differences
+            .iter()
+            .map(|difference| difference.describe())
+            .collect();
+        format!(
+            "looks like `{}` with {}",
+            self.command.to_brnfck(),
+            differences.join(" and ")
+        )
+    }
+}
+
+impl Difference {
+    pub fn describe(&self) -> String {
+        let row = ROW_NAMES[self.row];
+        if self.found == SPACE {
+            format!("a missing `{}` in the {} row", self.expected as char, row)
+        } else if self.expected == SPACE {
+            format!("an extra `{}` in the {} row", self.found as char, row)
+        } else {
+            format!(
+                "`{}` instead of `{}` in the {} row",
+                self.found as char, self.expected as char, row
+            )
+        }
+    }
+}
+
+pub fn suggestions(column: usize, rows: [&[u8]; 3]) -> Vec<Suggestion> {
+    let mut candidates: Vec<Suggestion> = Command::ALL
+        .iter()
+        .map(|command| Suggestion {
+            command: *command,
+            differences: differences(column, rows, *command),
+        })
+        .filter(|candidate| candidate.differences.len() <= MAX_DIFFERENCES)
+        .collect();
+    if let Some(fewest) = candidates
+        .iter()
+        .map(|candidate| candidate.differences.len())
+        .min()
+    {
+        candidates.retain(|candidate| candidate.differences.len() == fewest);
+    }
+    candidates
+}
+
+fn differences(column: usize, rows: [&[u8]; 3], command: Command) -> Vec<Difference> {
+    let glyph = [command.top(), command.middle(), command.bottom()];
+    let mut differences = vec![];
+    for (row, line) in glyph.iter().enumerate() {
+        for (offset, &expected) in line.as_bytes().iter().enumerate() {
+            let found = rows[row].get(column + offset).cloned().unwrap_or(SPACE);
+            if found != expected {
+                differences.push(Difference {
+                    row,
+                    column: column + offset,
+                    expected,
+                    found,
+                });
+            }
+        }
+    }
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_suggest_closest_mountain_range() {
+        let rows: [&[u8]; 3] = [
+            "  /\\    ".as_bytes(),
+            " /  \\/  ".as_bytes(),
+            "/      \\".as_bytes(),
+        ];
+
+        let suggestions = suggestions(0, rows);
+
+        assert_eq!(
+            suggestions,
+            vec![Suggestion {
+                command: Command::JumpAhead,
+                differences: vec![Difference {
+                    row: 1,
+                    column: 6,
+                    expected: 92,
+                    found: 32,
+                }],
+            }]
+        );
+        assert_eq!(
+            suggestions[0].describe(),
+            "looks like `[` with a missing `\\` in the middle row"
+        );
+    }
+
+    #[test]
+    fn should_not_suggest_distant_mountain_ranges() {
+        let rows: [&[u8]; 3] = ["xxxx".as_bytes(), "xxxx".as_bytes(), "xxxx".as_bytes()];
+
+        assert_eq!(suggestions(0, rows), vec![]);
+    }
+
+    #[test]
+    fn should_describe_replaced_characters() {
+        let difference = Difference {
+            row: 2,
+            column: 3,
+            expected: 92,
+            found: 95,
+        };
+
+        assert_eq!(
+            difference.describe(),
+            "`_` instead of `\\` in the bottom row"
+        );
+    }
+}
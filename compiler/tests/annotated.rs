@@ -0,0 +1,33 @@
+extern crate bergen;
+
+use bergen::brnfck::Command;
+use bergen::parser::parse;
+
+#[test]
+fn parse_annotated_programs() {
+    let sources: Vec<&[u8]> = vec![
+        include_bytes!("bergen/haiku.bergen"),
+        include_bytes!("../../docs/program/haiku.bergen"),
+        include_bytes!("../../docs/program/hello_joy_of_coding.bergen"),
+    ];
+
+    for source in sources {
+        assert!(parse(source).is_ok());
+    }
+}
+
+#[test]
+fn parse_program_with_trailing_annotation() {
+    let source: &[u8] = include_bytes!("bergen/simple.bergen");
+
+    assert_eq!(
+        parse(source),
+        Ok(vec![
+            Command::Increment,
+            Command::Increment,
+            Command::JumpAhead,
+            Command::Decrement,
+            Command::JumpBack,
+        ])
+    );
+}
@@ -0,0 +1,113 @@
+extern crate bergen;
+
+use bergen::brnfck::parser::parse;
+use bergen::brnfck::{io_run, to_c, CellWidth, Eof, MachineConfig, Overflow};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::{self, Command, Stdio};
+
+fn compiled(name: &str, source: &[u8], input: &[u8], config: MachineConfig) -> (Vec<u8>, String) {
+    let directory = env::temp_dir().join(format!("bergen-c-{}-{}", process::id(), name));
+    fs::create_dir_all(&directory).unwrap();
+    let (program, binary) = (directory.join("program.c"), directory.join("program"));
+    let mut c: Vec<u8> = vec![];
+    to_c(&parse(source).unwrap(), config, &mut c).unwrap();
+    fs::write(&program, c).unwrap();
+
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(compiler)
+        .arg("-o")
+        .arg(&binary)
+        .arg(&program)
+        .status()
+        .expect("a C compiler to be available");
+    assert!(status.success());
+
+    let mut child = Command::new(&binary)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let result = child.wait_with_output().unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+    (result.stdout, String::from_utf8(result.stderr).unwrap())
+}
+
+fn interpreted(source: &[u8], input: &[u8], config: MachineConfig) -> (Vec<u8>, String) {
+    let mut output: Vec<u8> = vec![];
+    let problem = match io_run(&parse(source).unwrap(), input, &mut output, config) {
+        Ok(()) => String::new(),
+        Err(error) => format!("error: {}\n", error.kind.describe()),
+    };
+    (output, problem)
+}
+
+#[test]
+fn compiled_c_should_behave_like_the_interpreter() {
+    let overflowing = "++[->+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++<]>.";
+    for (index, (source, input, config)) in [
+        (
+            &b"++++++[>++++++++<-]>+.+.+."[..],
+            &b""[..],
+            MachineConfig::default(),
+        ),
+        (&b",[.,]"[..], &b"bergen"[..], MachineConfig::default()),
+        (
+            &b",[.,]"[..],
+            &b"bergen"[..],
+            MachineConfig::default().eof(Eof::Zero),
+        ),
+        (&b"+[>+]"[..], &b""[..], MachineConfig::default().tape(300)),
+        (&b"-."[..], &b""[..], MachineConfig::default()),
+        (
+            &b"-."[..],
+            &b""[..],
+            MachineConfig::default().overflow(Overflow::Wrap),
+        ),
+        (
+            &b"-."[..],
+            &b""[..],
+            MachineConfig::default().overflow(Overflow::Saturate),
+        ),
+        (overflowing.as_bytes(), &b""[..], MachineConfig::default()),
+        (
+            overflowing.as_bytes(),
+            &b""[..],
+            MachineConfig::default().overflow(Overflow::Wrap),
+        ),
+        (
+            overflowing.as_bytes(),
+            &b""[..],
+            MachineConfig::default().overflow(Overflow::Saturate),
+        ),
+        (
+            &b"+[+>-<]>."[..],
+            &b""[..],
+            MachineConfig::default().overflow(Overflow::Wrap),
+        ),
+        (
+            &b"<<+[-<]>>>>+[>>]<<."[..],
+            &b""[..],
+            MachineConfig::default().unbounded(true),
+        ),
+        (
+            &b",.+."[..],
+            &b""[..],
+            MachineConfig::default()
+                .width(CellWidth::U16)
+                .eof(Eof::MinusOne)
+                .overflow(Overflow::Wrap),
+        ),
+    ]
+    .iter()
+    .enumerate()
+    {
+        assert_eq!(
+            compiled(&index.to_string(), source, input, *config),
+            interpreted(source, input, *config)
+        );
+    }
+}